@@ -0,0 +1,17 @@
+use crate::math::{Color, Vec3};
+
+// The radiance seen by rays that escape the scene, looked up by their direction
+pub trait Background: Sync + Send {
+    fn value(&self, direction: Vec3) -> Color;
+
+    // Backgrounds that can be sampled as lights pick a direction with roughly the light
+    // arriving from it, returning it with its density in solid angle
+    fn sample_direction(&self) -> Option<(Vec3, f64)> {
+        None
+    }
+
+    // The density with which `sample_direction` picks `direction`
+    fn pdf(&self, _direction: Vec3) -> f64 {
+        0.0
+    }
+}
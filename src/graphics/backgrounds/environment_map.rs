@@ -0,0 +1,162 @@
+use std::{f64::consts::PI, fs::File, io::BufReader, path::Path};
+
+use image::{GenericImageView, ImageResult, codecs::hdr::HdrDecoder};
+use rand::Rng;
+
+use crate::{graphics::models::Sphere, math::{Color, Distribution2D, Vec3}};
+
+use super::Background;
+
+// A latitude-longitude panorama lighting the scene from every direction.
+// Rows run from straight up at the top of the image to straight down at the bottom.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    rotation: f64,
+    intensity: f64,
+    distribution: Distribution2D,
+}
+
+impl EnvironmentMap {
+    pub fn new(filepath: &str) -> Self {
+        let (width, height, pixels) = match EnvironmentMap::load(filepath) {
+            Ok(image) => image,
+            Err(e) => {
+                eprintln!("{:?}", e);
+                (1, 1, vec![Color::new(1, 0, 1)])
+            }
+        };
+
+        EnvironmentMap::new_from_pixels(width, height, pixels)
+    }
+
+    pub fn new_from_pixels(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        // Weight each pixel by the solid angle it covers, which shrinks towards the poles
+        let weights: Vec<f64> = pixels
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let row = i / width;
+                let sin_theta = (PI * (row as f64 + 0.5) / height as f64).sin();
+                p.luminance() * sin_theta
+            })
+            .collect();
+
+        EnvironmentMap {
+            width,
+            height,
+            pixels,
+            rotation: 0.0,
+            intensity: 1.0,
+            distribution: Distribution2D::new(&weights, width, height),
+        }
+    }
+
+    // Rotates the panorama about the vertical axis, in degrees
+    pub fn set_rotation<T>(self, rotation: T) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        EnvironmentMap {
+            rotation: rotation.into() * PI / 180.0,
+            ..self
+        }
+    }
+
+    pub fn set_intensity<T>(self, intensity: T) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        EnvironmentMap {
+            intensity: intensity.into(),
+            ..self
+        }
+    }
+
+    fn rotate(direction: Vec3, angle: f64) -> Vec3 {
+        let (sin, cos) = angle.sin_cos();
+        Vec3::new(
+            cos * direction.x + sin * direction.z,
+            direction.y,
+            -sin * direction.x + cos * direction.z,
+        )
+    }
+
+    fn load(filepath: &str) -> ImageResult<(usize, usize, Vec<Color>)> {
+        let is_hdr = Path::new(filepath)
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("hdr"));
+
+        if is_hdr {
+            let decoder = HdrDecoder::new(BufReader::new(File::open(filepath)?))?;
+            let metadata = decoder.metadata();
+            let pixels = decoder
+                .read_image_hdr()?
+                .iter()
+                .map(|p| Color::new(p[0], p[1], p[2]))
+                .collect();
+
+            Ok((metadata.width as usize, metadata.height as usize, pixels))
+        } else {
+            let image = image::open(filepath)?;
+            let (width, height) = image.dimensions();
+            let pixels = image
+                .to_rgb8()
+                .pixels()
+                .map(|p| Color::new(p[0] as f64 / 255.0, p[1] as f64 / 255.0, p[2] as f64 / 255.0))
+                .collect();
+
+            Ok((width as usize, height as usize, pixels))
+        }
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn value(&self, direction: Vec3) -> Color {
+        let local_direction = EnvironmentMap::rotate(direction.normalize(), -self.rotation);
+        let (u, v) = Sphere::get_uv(local_direction);
+
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = (((1.0 - v) * self.height as f64) as usize).min(self.height - 1);
+
+        self.intensity * self.pixels[j * self.width + i]
+    }
+
+    // Picks a direction with probability roughly proportional to the light arriving from it,
+    // returning it with its density in solid angle
+    fn sample_direction(&self) -> Option<(Vec3, f64)> {
+        let mut rng = rand::thread_rng();
+        let ((column, row), map_pdf) = self.distribution.sample_continuous(rng.gen(), rng.gen());
+
+        let theta = (1.0 - row) * PI;
+        let phi = column * 2.0 * PI;
+        let sin_theta = theta.sin();
+
+        let local_direction = Vec3::new(
+            -phi.cos() * sin_theta,
+            -theta.cos(),
+            phi.sin() * sin_theta,
+        );
+        let direction = EnvironmentMap::rotate(local_direction, self.rotation);
+
+        if sin_theta == 0.0 {
+            return Some((direction, 0.0));
+        }
+
+        Some((direction, map_pdf / (2.0 * PI * PI * sin_theta)))
+    }
+
+    fn pdf(&self, direction: Vec3) -> f64 {
+        let local_direction = EnvironmentMap::rotate(direction.normalize(), -self.rotation);
+        let (u, v) = Sphere::get_uv(local_direction);
+
+        let sin_theta = (v * PI).sin();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+
+        self.distribution.pdf(u, 1.0 - v) / (2.0 * PI * PI * sin_theta)
+    }
+}
+
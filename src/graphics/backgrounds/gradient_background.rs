@@ -0,0 +1,35 @@
+use crate::math::{Color, Vec3};
+
+use super::Background;
+
+// Blends vertically from the bottom colour, looking straight down, to the top colour
+pub struct GradientBackground {
+    bottom: Color,
+    top: Color,
+}
+
+impl GradientBackground {
+    pub fn new(bottom: Color, top: Color) -> Self {
+        GradientBackground {
+            bottom,
+            top,
+        }
+    }
+
+    pub fn sky() -> Self {
+        GradientBackground::new(Color::ONE, Color::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Background for GradientBackground {
+    fn value(&self, direction: Vec3) -> Color {
+        let t = 0.5 * (direction.normalize().y + 1.0);
+        (1.0 - t) * self.bottom + t * self.top
+    }
+}
+
+impl Default for GradientBackground {
+    fn default() -> Self {
+        GradientBackground::sky()
+    }
+}
@@ -0,0 +1,13 @@
+mod background;
+
+mod environment_map;
+mod gradient_background;
+mod solid_background;
+mod texture_background;
+
+pub use background::Background;
+
+pub use environment_map::EnvironmentMap;
+pub use gradient_background::GradientBackground;
+pub use solid_background::SolidBackground;
+pub use texture_background::TextureBackground;
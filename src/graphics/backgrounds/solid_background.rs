@@ -0,0 +1,21 @@
+use crate::math::{Color, Vec3};
+
+use super::Background;
+
+pub struct SolidBackground {
+    color: Color,
+}
+
+impl SolidBackground {
+    pub fn new(color: Color) -> Self {
+        SolidBackground {
+            color,
+        }
+    }
+}
+
+impl Background for SolidBackground {
+    fn value(&self, _direction: Vec3) -> Color {
+        self.color
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{graphics::{models::Sphere, textures::Texture}, math::{Color, Vec3}};
+
+use super::Background;
+
+// Wraps a texture around the scene, using the same mapping as a sphere's surface
+pub struct TextureBackground {
+    texture: Box<dyn Texture>,
+}
+
+impl TextureBackground {
+    pub fn new(texture: Box<dyn Texture>) -> Self {
+        TextureBackground {
+            texture,
+        }
+    }
+}
+
+impl Background for TextureBackground {
+    fn value(&self, direction: Vec3) -> Color {
+        let direction = direction.normalize();
+        let (u, v) = Sphere::get_uv(direction);
+        self.texture.value(u, v, direction)
+    }
+}
@@ -70,7 +70,7 @@ impl Hittable for Bvh {
         Some(self.bounding_box)
     }
 
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         if !self.bounding_box.hit(ray, t_min, t_max) {
             return None;
         }
@@ -95,4 +95,41 @@ impl Hittable for Bvh {
             }
         }
     }
+
+    fn hit_surface(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        if !self.bounding_box.hit(ray, t_min, t_max) {
+            return None;
+        }
+
+        match &self.node {
+            BvhNode::Leaf(n) => n.hit_surface(ray, t_min, t_max),
+            BvhNode::Branch{left, right} => {
+                let hit_left = left.hit_surface(ray, t_min, t_max);
+                let hit_right = right.hit_surface(ray, t_min, t_max);
+                match (hit_left, hit_right) {
+                    (Some(left_record), Some(right_record)) => {
+                        if left_record.t < right_record.t {
+                            Some(left_record)
+                        } else {
+                            Some(right_record)
+                        }
+                    }
+                    (left_record, right_record) => left_record.or(right_record),
+                }
+            }
+        }
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        if !self.bounding_box.hit(ray, t_min, t_max) {
+            return 1.0;
+        }
+
+        match &self.node {
+            BvhNode::Leaf(n) => n.transmittance(ray, t_min, t_max),
+            BvhNode::Branch{left, right} => {
+                left.transmittance(ray, t_min, t_max) * right.transmittance(ray, t_min, t_max)
+            }
+        }
+    }
 }
\ No newline at end of file
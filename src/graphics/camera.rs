@@ -12,6 +12,8 @@ pub struct Camera {
     vertical: Vec3,
     u: Vec3,
     v: Vec3,
+    w: Vec3,
+    focus_dist: f64,
     lens_radius: f64,
     time0: f64,
     time1: f64,
@@ -22,6 +24,7 @@ impl Camera {
         deg * PI / 180.0
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn new<T>(
         position: Point,
         look_at: Vec3,
@@ -59,6 +62,8 @@ impl Camera {
             vertical,
             u,
             v,
+            w,
+            focus_dist,
             lens_radius,
             time0,
             time1,
@@ -66,15 +71,88 @@ impl Camera {
     }
 
     pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * Vec3::random_in_unit_disk();
-        let offset: Vec3 = self.u * rd.x + self.v * rd.y;
-
-        let mut rng = rand::thread_rng();
+        let lens_point = self.sample_lens();
 
         Ray::new(
-            self.origin + offset,
-            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
-            rng.gen_range(self.time0..self.time1),
+            lens_point,
+            self.lower_left_corner + s * self.horizontal + t * self.vertical - lens_point,
+            self.sample_time(),
         )
     }
+
+    // A random moment while the shutter is open
+    pub fn sample_time(&self) -> f64 {
+        rand::thread_rng().gen_range(self.time0..self.time1)
+    }
+
+    // A random point on the lens, where every ray from the camera starts
+    pub fn sample_lens(&self) -> Point {
+        let rd = self.lens_radius * Vec3::random_in_unit_disk();
+        self.origin + self.u * rd.x + self.v * rd.y
+    }
+
+    // The screen coordinates, as taken by `get_ray`, of the ray from `lens_point` through
+    // `p`. They fall outside [0, 1] when `p` is out of view, and there are none when it is
+    // behind the camera.
+    pub fn project(&self, lens_point: Point, p: Point) -> Option<(f64, f64)> {
+        let direction = p - lens_point;
+        let forward_distance = -direction.dot(self.w);
+        if forward_distance <= 0.0 {
+            return None;
+        }
+
+        // The viewport lies in the plane in focus
+        let offset = lens_point + direction * (self.focus_dist / forward_distance) - self.lower_left_corner;
+        Some((
+            offset.dot(self.horizontal) / self.horizontal.length_squared(),
+            offset.dot(self.vertical) / self.vertical.length_squared(),
+        ))
+    }
+
+    // How much a unit of light arriving at the lens along `direction` counts towards the
+    // image, spread so it adds up to one over the lens and the whole viewport
+    pub fn importance(&self, direction: Vec3) -> f64 {
+        let cos_theta = -direction.normalize().dot(self.w);
+        if cos_theta <= 0.0 {
+            return 0.0;
+        }
+
+        1.0 / (self.viewport_area() * self.lens_area() * cos_theta.powi(4))
+    }
+
+    // The density in solid angle with which `get_ray` picks `direction`, given the point
+    // on the lens, when the screen coordinates are uniform over the viewport
+    pub fn pdf_direction(&self, direction: Vec3) -> f64 {
+        let cos_theta = -direction.normalize().dot(self.w);
+        if cos_theta <= 0.0 {
+            return 0.0;
+        }
+
+        1.0 / (self.viewport_area() * cos_theta.powi(3))
+    }
+
+    // The density in solid angle, as seen from `p`, with which `sample_lens` picks `lens_point`
+    pub fn pdf_lens(&self, lens_point: Point, p: Point) -> f64 {
+        let direction = p - lens_point;
+        let cos_theta = -direction.normalize().dot(self.w);
+        if cos_theta <= 0.0 {
+            return 0.0;
+        }
+
+        direction.length_squared() / (cos_theta * self.lens_area())
+    }
+
+    // A pinhole lens is treated as having unit area, which cancels out wherever it is used
+    fn lens_area(&self) -> f64 {
+        if self.lens_radius > 0.0 {
+            PI * self.lens_radius * self.lens_radius
+        } else {
+            1.0
+        }
+    }
+
+    // The area of the viewport scaled to one unit in front of the lens
+    fn viewport_area(&self) -> f64 {
+        self.horizontal.length() * self.vertical.length() / (self.focus_dist * self.focus_dist)
+    }
 }
@@ -1,7 +1,18 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
 use crate::math::{Point, Vec3};
 
 use super::{Aabb, Ray, materials::Material};
 
+// Which mesh vertices surround a hit, and how much each one contributes to it
+#[derive(Clone, Copy, Debug)]
+pub struct Barycentrics {
+    pub vertices: [usize; 3],
+    pub weights: [f64; 3],
+}
+
 pub struct HitRecord<'a> {
     pub p: Point,
     pub normal: Vec3,
@@ -10,6 +21,9 @@ pub struct HitRecord<'a> {
     pub v: f64,
     pub front_face: bool,
     pub material: &'a dyn Material,
+    pub barycentrics: Option<Barycentrics>,
+    // The direction u increases in across the surface, for surfaces which have one
+    pub tangent: Option<Vec3>,
 }
 
 impl<'a> HitRecord<'a> {
@@ -35,28 +49,123 @@ impl<'a> HitRecord<'a> {
             v,
             front_face,
             material,
+            barycentrics: None,
+            tangent: None,
         }
     }
 }
 
 pub trait Hittable: Sync + Send {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
 
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb>;
+
+    // The density in solid angle with which `random` picks a direction from `origin`
+    // towards this object. Objects that can't be sampled as lights leave it at zero.
+    fn pdf_value(&self, _origin: Point, _direction: Vec3) -> f64 {
+        0.0
+    }
+
+    // A random direction from `origin` towards this object
+    fn random(&self, _origin: Point) -> Vec3 {
+        Vec3::RIGHT
+    }
+
+    // The area `sample_surface` picks points from, so zero for objects which can't
+    // start paths as lights
+    fn area(&self) -> f64 {
+        0.0
+    }
+
+    // A point picked uniformly over the surface, as if hit from outside, for starting
+    // paths from emitters
+    fn sample_surface(&self) -> Option<HitRecord<'_>> {
+        None
+    }
+
+    // The closest hit along a shadow ray. Media which estimate how much light they let
+    // through with `transmittance` are seen through rather than hit.
+    fn hit_surface(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.hit(ray, t_min, t_max)
+    }
+
+    // The fraction of light getting through the media `hit_surface` sees through
+    fn transmittance(&self, _ray: &Ray, _t_min: f64, _t_max: f64) -> f64 {
+        1.0
+    }
 }
 
 impl Hittable for Box<dyn Hittable> {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        (**self).hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        (**self).bounding_box(time0, time1)
+    }
+
+    fn pdf_value(&self, origin: Point, direction: Vec3) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Point) -> Vec3 {
+        (**self).random(origin)
+    }
+
+    fn area(&self) -> f64 {
+        (**self).area()
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord<'_>> {
+        (**self).sample_surface()
+    }
+
+    fn hit_surface(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        (**self).hit_surface(ray, t_min, t_max)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        (**self).transmittance(ray, t_min, t_max)
+    }
+}
+
+// Lets a prototype, such as a mesh, be shared between many instances
+impl Hittable for Arc<dyn Hittable> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         (**self).hit(ray, t_min, t_max)
     }
 
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
         (**self).bounding_box(time0, time1)
     }
+
+    fn pdf_value(&self, origin: Point, direction: Vec3) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Point) -> Vec3 {
+        (**self).random(origin)
+    }
+
+    fn area(&self) -> f64 {
+        (**self).area()
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord<'_>> {
+        (**self).sample_surface()
+    }
+
+    fn hit_surface(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        (**self).hit_surface(ray, t_min, t_max)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        (**self).transmittance(ray, t_min, t_max)
+    }
 }
 
 impl Hittable for Vec<Box<dyn Hittable>> {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         let mut current_record = None;
         let mut closest_so_far = t_max;
 
@@ -94,5 +203,59 @@ impl Hittable for Vec<Box<dyn Hittable>> {
         }
 
         Some(output_box)
-    }   
+    }
+
+    // Lists of lights are sampled by picking one of them at random
+    fn pdf_value(&self, origin: Point, direction: Vec3) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+
+        self.iter().map(|o| o.pdf_value(origin, direction)).sum::<f64>() / self.len() as f64
+    }
+
+    fn random(&self, origin: Point) -> Vec3 {
+        if self.is_empty() {
+            return Vec3::RIGHT;
+        }
+
+        self[rand::thread_rng().gen_range(0..self.len())].random(origin)
+    }
+
+    fn area(&self) -> f64 {
+        self.iter().map(|o| o.area()).sum()
+    }
+
+    // Surfaces are picked in proportion to their area, so every point is equally likely
+    fn sample_surface(&self) -> Option<HitRecord<'_>> {
+        let mut remaining = rand::thread_rng().gen::<f64>() * self.area();
+
+        for object in self.iter() {
+            let area = object.area();
+            if remaining < area {
+                return object.sample_surface();
+            }
+            remaining -= area;
+        }
+
+        None
+    }
+
+    fn hit_surface(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut current_record = None;
+        let mut closest_so_far = t_max;
+
+        for object in self.iter() {
+            if let Some(h) = object.hit_surface(ray, t_min, closest_so_far) {
+                closest_so_far = h.t;
+                current_record = Some(h);
+            }
+        }
+
+        current_record
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.iter().map(|o| o.transmittance(ray, t_min, t_max)).product()
+    }
 }
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use crate::math::{Matrix4, Point, Vec3};
+
+use super::{Aabb, HitRecord, Hittable, Ray, Transform, materials::Material};
+
+// One placement of a shared prototype. Many instances can reference the same hittable,
+// such as a mesh, each with its own transform and optionally its own material.
+// Instances are usually gathered into a `Bvh`, which then acts as a top level BVH
+// over the instance bounds.
+pub struct Instance {
+    prototype: Arc<dyn Hittable>,
+    matrix: Matrix4,
+    inverse: Matrix4,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Instance {
+    pub fn new(prototype: Arc<dyn Hittable>, matrix: Matrix4) -> Self {
+        let inverse = match matrix.inverse() {
+            Some(i) => i,
+            None => panic!("Instance matrix cannot be inverted"),
+        };
+
+        Instance {
+            prototype,
+            matrix,
+            inverse,
+            material: None,
+        }
+    }
+
+    // Replaces the material of every surface of the prototype for this instance only
+    pub fn set_material(self, material: Arc<dyn Material>) -> Self {
+        Instance {
+            material: Some(material),
+            ..self
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4 {
+        self.matrix
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut hit = Transform::hit_transformed(&*self.prototype, &self.matrix, &self.inverse, ray, t_min, t_max)?;
+
+        if let Some(material) = &self.material {
+            hit.material = &**material;
+        }
+
+        Some(hit)
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.prototype
+            .bounding_box(time0, time1)
+            .map(|b| Transform::transform_box(&self.matrix, &b))
+    }
+
+    fn pdf_value(&self, origin: Point, direction: Vec3) -> f64 {
+        Transform::pdf_value_transformed(&*self.prototype, &self.inverse, origin, direction)
+    }
+
+    fn random(&self, origin: Point) -> Vec3 {
+        Transform::random_transformed(&*self.prototype, &self.matrix, &self.inverse, origin)
+    }
+
+    fn area(&self) -> f64 {
+        self.prototype.area() * Transform::max_area_scale(&self.matrix, &self.inverse)
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord<'_>> {
+        let mut hit = Transform::sample_surface_transformed(&*self.prototype, &self.matrix, &self.inverse)?;
+
+        if let Some(material) = &self.material {
+            hit.material = &**material;
+        }
+
+        Some(hit)
+    }
+
+    fn hit_surface(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut hit = Transform::hit_surface_transformed(&*self.prototype, &self.matrix, &self.inverse, ray, t_min, t_max)?;
+
+        if let Some(material) = &self.material {
+            hit.material = &**material;
+        }
+
+        Some(hit)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        Transform::transmittance_transformed(&*self.prototype, &self.inverse, ray, t_min, t_max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graphics::{materials::{DiffuseLight, Lambertian}, models::XzRect},
+        math::Color,
+    };
+
+    #[test]
+    fn instanced_lights_can_be_sampled() {
+        let panel: Arc<dyn Hittable> = Arc::new(XzRect::new(-0.5, 0.5, -0.5, 0.5, 0.0, Box::new(Lambertian::new_from_color(Color::ONE))));
+        let light = Instance::new(panel, Matrix4::translation(Vec3::new(0.0, 5.0, 0.0)) * Matrix4::scaling(Vec3::ONE * 2.0))
+            .set_material(Arc::new(DiffuseLight::new_from_color(Color::ONE * 4.0)));
+
+        let origin = Point::ZERO;
+        let direction = light.random(origin);
+        assert!(light.hit(&Ray::new(origin, direction, 0.0), 0.001, f64::INFINITY).is_some());
+
+        // A 2x2 panel 5 away, seen head on from straight below
+        let expected = 25.0 / 4.0;
+        assert!((light.pdf_value(origin, Vec3::UP) - expected).abs() < 1e-9);
+        assert!((light.area() - 4.0).abs() < 1e-9);
+
+        let hit = light.sample_surface().unwrap();
+        assert!((hit.p.y - 5.0).abs() < 1e-9);
+        assert!(hit.material.emitted(hit.u, hit.v, hit.p).x > 0.0);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    graphics::{HitRecord, Ray},
+    math::{Color, Onb},
+};
+
+use super::{Material, MaterialId, microfacet::{TrowbridgeReitz, to_local}};
+
+// A metal made of tiny mirror facets facing in directions given by the GGX (Trowbridge-
+// Reitz) distribution, reflecting by the Fresnel equations for its complex index of
+// refraction, eta + ik, in each colour channel. Roughness is the GGX alpha, from 0 for a
+// mirror to 1 for very rough, and can differ along the two tangents for brushed metals.
+// The first tangent follows the direction u increases in on surfaces which give one.
+pub struct Conductor {
+    eta: Color,
+    k: Color,
+    distribution: TrowbridgeReitz,
+    id: MaterialId,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Conductor {
+        Conductor {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::new(roughness, roughness),
+            id: MaterialId::new(),
+        }
+    }
+
+    // A conductor with the given reflectance when seen head on, for when the optical
+    // constants aren't known
+    pub fn new_from_color(reflectance: Color, roughness: f64) -> Conductor {
+        let eta = |r: f64| {
+            let r = r.clamp(0.0, 0.999).sqrt();
+            (1.0 + r) / (1.0 - r)
+        };
+        Conductor::new(Color::new(eta(reflectance.x), eta(reflectance.y), eta(reflectance.z)), Color::ZERO, roughness)
+    }
+
+    // Roughness along and across the direction u increases in, so brushing along u gives a
+    // small first roughness and a larger second one
+    pub fn set_anisotropic_roughness(self, roughness_x: f64, roughness_y: f64) -> Self {
+        Conductor {
+            distribution: TrowbridgeReitz::new(roughness_x, roughness_y),
+            ..self
+        }
+    }
+
+    // Optical constants for red, green and blue light, at about 650, 550 and 450 nm
+    pub fn gold(roughness: f64) -> Conductor {
+        Conductor::new(Color::new(0.143, 0.374, 1.442), Color::new(3.983, 2.385, 1.603), roughness)
+    }
+
+    pub fn copper(roughness: f64) -> Conductor {
+        Conductor::new(Color::new(0.200, 0.924, 1.102), Color::new(3.912, 2.452, 2.142), roughness)
+    }
+
+    pub fn aluminium(roughness: f64) -> Conductor {
+        Conductor::new(Color::new(1.657, 0.880, 0.521), Color::new(9.224, 6.270, 4.837), roughness)
+    }
+
+    pub fn silver(roughness: f64) -> Conductor {
+        Conductor::new(Color::new(0.155, 0.117, 0.138), Color::new(4.828, 3.122, 2.147), roughness)
+    }
+
+    fn frame(hit: &HitRecord) -> Onb {
+        match hit.tangent {
+            Some(tangent) => Onb::new_from_w_and_u(hit.normal, tangent),
+            None => Onb::new_from_w(hit.normal),
+        }
+    }
+
+    fn fresnel(&self, cos_theta: f64) -> Color {
+        Color::new(
+            fresnel_conductor(cos_theta, self.eta.x, self.k.x),
+            fresnel_conductor(cos_theta, self.eta.y, self.k.y),
+            fresnel_conductor(cos_theta, self.eta.z, self.k.z),
+        )
+    }
+}
+
+// Unpolarised Fresnel reflectance from a medium with complex index eta + ik
+fn fresnel_conductor(cos_theta: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_theta.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+
+    let t0 = eta * eta - k * k - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+
+    let t1 = a2_plus_b2 + cos2;
+    let t2 = 2.0 * a * cos_theta;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    0.5 * (rs + rp)
+}
+
+impl Material for Conductor {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Color)> {
+        if self.distribution.is_smooth() {
+            let cos_theta = (-ray.direction).dot(hit.normal);
+            let reflected = ray.direction.reflect(hit.normal);
+            return Some((Ray::new(hit.p, reflected, ray.time), self.fresnel(cos_theta)));
+        }
+
+        let onb = Conductor::frame(hit);
+        let wo = to_local(&onb, -ray.direction);
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let h = self.distribution.sample_visible_normal(wo);
+        let wi = (-wo).reflect(h);
+        if wi.z <= 0.0 {
+            return None;
+        }
+
+        // f cos / pdf, with everything but the Fresnel and masking cancelling out
+        let attenuation = self.fresnel(wo.dot(h)) * (self.distribution.g(wo, wi) / self.distribution.g1(wo));
+        Some((Ray::new(hit.p, onb.local(wi), ray.time), attenuation))
+    }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, scattered: &Ray) -> Color {
+        if self.distribution.is_smooth() {
+            return Color::ZERO;
+        }
+
+        let onb = Conductor::frame(hit);
+        let wo = to_local(&onb, -ray.direction);
+        let wi = to_local(&onb, scattered.direction);
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::ZERO;
+        }
+
+        let h = (wo + wi).normalize();
+        let d = self.distribution.d(h);
+        let g = self.distribution.g(wo, wi);
+        self.fresnel(wo.dot(h)) * (d * g / (4.0 * wo.z))
+    }
+
+    fn scattering_pdf(&self, ray: &Ray, hit: &HitRecord, scattered: &Ray) -> f64 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+
+        let onb = Conductor::frame(hit);
+        let wo = to_local(&onb, -ray.direction);
+        let wi = to_local(&onb, scattered.direction);
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+
+        let h = (wo + wi).normalize();
+        self.distribution.visible_pdf(wo, h) / (4.0 * wo.dot(h))
+    }
+
+    fn id(&self) -> usize {
+        self.id.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Point, Vec3};
+
+    #[test]
+    fn anisotropy_follows_the_surface_tangent() {
+        let brushed_along_u = Conductor::gold(0.5).set_anisotropic_roughness(0.05, 0.5);
+        let brushed_across_u = Conductor::gold(0.5).set_anisotropic_roughness(0.5, 0.05);
+
+        let ray = Ray::new(Point::new(-1.0, 1.0, 0.3), Vec3::new(1.0, -1.0, -0.3), 0.0);
+        let scattered = Ray::new(Point::ZERO, Vec3::new(0.7, 1.0, 0.4), 0.0);
+        let hit_with_tangent = |tangent: Vec3| {
+            let mut hit = HitRecord::new(Point::ZERO, Vec3::UP, 1.0, 0.0, 0.0, &ray, &brushed_along_u);
+            hit.tangent = Some(tangent);
+            hit
+        };
+
+        // Turning the tangent a quarter turn is the same as swapping the roughnesses
+        let along = brushed_along_u.eval(&ray, &hit_with_tangent(Vec3::RIGHT), &scattered);
+        let across = brushed_across_u.eval(&ray, &hit_with_tangent(Vec3::FORWARD), &scattered);
+        assert!(along.x > 0.0);
+        assert!((along - across).near_zero());
+
+        // The tangent doesn't need to lie in the surface, only its projection is used
+        let tilted = brushed_along_u.eval(&ray, &hit_with_tangent(Vec3::new(2.0, 5.0, 0.0)), &scattered);
+        assert!((along - tilted).near_zero());
+
+        // And it changes how the light spreads
+        let turned = brushed_along_u.eval(&ray, &hit_with_tangent(Vec3::FORWARD), &scattered);
+        assert!((along - turned).length() > 0.01 * along.length());
+    }
+}
@@ -3,17 +3,86 @@ use crate::{
     math::Color,
 };
 
-use super::Material;
+use super::{Material, MaterialId};
 
 use rand::Rng;
 
+// How the index of refraction changes with wavelength. Wavelengths are in micrometres in
+// the formulas, as glass catalogues give their coefficients for.
+#[derive(Clone, Copy, Debug)]
+pub enum Dispersion {
+    Constant(f64),
+    // n = a + b / λ²
+    Cauchy { a: f64, b: f64 },
+    // n² = 1 + Σ bᵢ λ² / (λ² - cᵢ)
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    // The wavelength of the helium d line, which catalogues quote a single index at
+    const REFERENCE_WAVELENGTH: f64 = 587.56;
+
+    // Schott N-BK7, the most common optical glass
+    pub fn bk7() -> Self {
+        Dispersion::Sellmeier {
+            b: [1.03961212, 0.231792344, 1.01046945],
+            c: [0.00600069867, 0.0200179144, 103.560653],
+        }
+    }
+
+    // Schott N-SF11, a dense flint glass which spreads colours a lot more than BK7
+    pub fn sf11() -> Self {
+        Dispersion::Sellmeier {
+            b: [1.73759695, 0.313747346, 1.89878101],
+            c: [0.013188707, 0.0623068142, 155.23629],
+        }
+    }
+
+    pub fn diamond() -> Self {
+        Dispersion::Sellmeier {
+            b: [4.3356, 0.3306, 0.0],
+            c: [0.01123600, 0.030625, 0.0],
+        }
+    }
+
+    // The index of refraction at a wavelength in nanometres
+    pub fn ior(&self, wavelength: f64) -> f64 {
+        let lambda2 = (wavelength / 1000.0).powi(2);
+        match self {
+            Dispersion::Constant(ir) => *ir,
+            Dispersion::Cauchy { a, b } => a + b / lambda2,
+            Dispersion::Sellmeier { b, c } => {
+                let n2 = 1.0 + (0..3).map(|i| b[i] * lambda2 / (lambda2 - c[i])).sum::<f64>();
+                n2.sqrt()
+            }
+        }
+    }
+}
+
 pub struct Dielectric {
-    ir: f64,
+    dispersion: Dispersion,
+    id: MaterialId,
 }
 
 impl Dielectric {
     pub fn new(ir: f64) -> Dielectric {
-        Dielectric { ir }
+        Dielectric {
+            dispersion: Dispersion::Constant(ir),
+            id: MaterialId::new(),
+        }
+    }
+
+    // Glass which bends each wavelength by a different amount, splitting white light into
+    // colours under a spectral integrator. RGB integrators use the index at the d line.
+    pub fn new_dispersive(dispersion: Dispersion) -> Dielectric {
+        Dielectric {
+            dispersion,
+            id: MaterialId::new(),
+        }
+    }
+
+    fn ir(&self, ray: &Ray) -> f64 {
+        self.dispersion.ior(ray.wavelength.unwrap_or(Dispersion::REFERENCE_WAVELENGTH))
     }
 }
 
@@ -31,11 +100,8 @@ impl Material for Dielectric {
         let mut rng = rand::thread_rng();
 
         let attenuation = Color::ONE;
-        let refraction_ratio = if hit.front_face {
-            1.0 / self.ir
-        } else {
-            self.ir
-        };
+        let ir = self.ir(ray);
+        let refraction_ratio = if hit.front_face { 1.0 / ir } else { ir };
 
         let unit_direction = ray.direction;
         let cos_theta = (-unit_direction).dot(hit.normal).min(1.0);
@@ -52,4 +118,11 @@ impl Material for Dielectric {
 
         Some((Ray::new(hit.p, direction, ray.time), attenuation))
     }
+
+    fn is_dispersive(&self) -> bool {
+        !matches!(self.dispersion, Dispersion::Constant(_))
+    }
+    fn id(&self) -> usize {
+        self.id.get()
+    }
 }
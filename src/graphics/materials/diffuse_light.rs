@@ -0,0 +1,37 @@
+use crate::{graphics::{HitRecord, Ray, textures::{SolidColor, Texture}}, math::{Color, Point}};
+
+use super::{Material, MaterialId};
+
+pub struct DiffuseLight {
+    emit: Box<dyn Texture>,
+    id: MaterialId,
+}
+
+impl DiffuseLight {
+    pub fn new(texture: Box<dyn Texture>) -> DiffuseLight {
+        DiffuseLight {
+            emit: texture,
+            id: MaterialId::new(),
+        }
+    }
+
+    pub fn new_from_color(color: Color) -> DiffuseLight {
+        DiffuseLight {
+            emit: Box::new(SolidColor::new(color)),
+            id: MaterialId::new(),
+        }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _ray: &Ray, _hit: &HitRecord) -> Option<(Ray, Color)> {
+        None
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: Point) -> Color {
+        self.emit.value(u, v, p)
+    }
+    fn id(&self) -> usize {
+        self.id.get()
+    }
+}
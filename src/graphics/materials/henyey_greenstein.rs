@@ -0,0 +1,108 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::{graphics::{HitRecord, Ray, textures::{SolidColor, Texture}}, math::{Color, Onb, Point, Vec3}};
+
+use super::{Material, MaterialId};
+
+// A phase function for participating media which favours scattering forwards when the
+// anisotropy g is positive and backwards when it is negative. A g of zero is isotropic.
+pub struct HenyeyGreenstein {
+    albedo: Box<dyn Texture>,
+    emission: Option<Box<dyn Texture>>,
+    g: f64,
+    id: MaterialId,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(texture: Box<dyn Texture>, g: f64) -> HenyeyGreenstein {
+        HenyeyGreenstein {
+            albedo: texture,
+            emission: None,
+            g: g.clamp(-0.99, 0.99),
+            id: MaterialId::new(),
+        }
+    }
+
+    pub fn new_from_color(albedo: Color, g: f64) -> HenyeyGreenstein {
+        HenyeyGreenstein::new(Box::new(SolidColor::new(albedo)), g)
+    }
+
+    // Light given off where the medium absorbs light, for fire and explosions
+    pub fn set_emission(self, emission: Box<dyn Texture>) -> Self {
+        HenyeyGreenstein {
+            emission: Some(emission),
+            ..self
+        }
+    }
+
+    pub fn set_anisotropy(self, g: f64) -> Self {
+        HenyeyGreenstein {
+            g: g.clamp(-0.99, 0.99),
+            ..self
+        }
+    }
+
+    // Samples the cosine of the angle between the incoming and scattered directions
+    fn sample_cos_theta(&self, xi: f64) -> f64 {
+        let g = self.g;
+        if g.abs() < 1e-3 {
+            return 1.0 - 2.0 * xi;
+        }
+
+        let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+        ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+
+    fn phase(&self, cos_theta: f64) -> f64 {
+        let g = self.g;
+        let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Color)> {
+        let mut rng = rand::thread_rng();
+
+        let cos_theta = self.sample_cos_theta(rng.gen());
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.gen::<f64>();
+
+        // The angle is measured from the direction the ray was travelling in
+        let direction = Onb::new_from_w(ray.direction).local(Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ));
+        let scattered_ray = Ray::new(hit.p, direction, ray.time);
+
+        Some((scattered_ray, self.albedo.value_at(hit)))
+    }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, scattered: &Ray) -> Color {
+        self.albedo.value_at(hit) * self.scattering_pdf(ray, hit, scattered)
+    }
+
+    fn scattering_pdf(&self, ray: &Ray, _hit: &HitRecord, scattered: &Ray) -> f64 {
+        self.phase(ray.direction.dot(scattered.direction))
+    }
+
+    fn is_volumetric(&self) -> bool {
+        true
+    }
+
+    // Only the collisions which absorb rather than scatter emit, so the emission is
+    // weighted by the chance of that, one minus the albedo
+    fn emitted(&self, u: f64, v: f64, p: Point) -> Color {
+        match &self.emission {
+            Some(e) => e.value(u, v, p) * (Color::ONE - self.albedo.value(u, v, p)),
+            None => Color::ZERO,
+        }
+    }
+
+    fn id(&self) -> usize {
+        self.id.get()
+    }
+}
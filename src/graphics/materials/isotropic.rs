@@ -0,0 +1,51 @@
+use std::f64::consts::PI;
+
+use crate::{graphics::{HitRecord, Ray, textures::{SolidColor, Texture}}, math::{Color, Vec3}};
+
+use super::{Material, MaterialId};
+
+// Scatters light equally in every direction, for the inside of participating media
+pub struct Isotropic {
+    albedo: Box<dyn Texture>,
+    id: MaterialId,
+}
+
+impl Isotropic {
+    pub fn new(texture: Box<dyn Texture>) -> Isotropic {
+        Isotropic {
+            albedo: texture,
+            id: MaterialId::new(),
+        }
+    }
+
+    pub fn new_from_color(albedo: Color) -> Isotropic {
+        Isotropic {
+            albedo: Box::new(SolidColor::new(albedo)),
+            id: MaterialId::new(),
+        }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Color)> {
+        let scattered_ray = Ray::new(hit.p, Vec3::random_unit_vector(), ray.time);
+        let attenuation = self.albedo.value_at(hit);
+
+        Some((scattered_ray, attenuation))
+    }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, scattered: &Ray) -> Color {
+        self.albedo.value_at(hit) * self.scattering_pdf(ray, hit, scattered)
+    }
+
+    fn scattering_pdf(&self, _ray: &Ray, _hit: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn is_volumetric(&self) -> bool {
+        true
+    }
+    fn id(&self) -> usize {
+        self.id.get()
+    }
+}
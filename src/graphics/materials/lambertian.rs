@@ -1,22 +1,27 @@
+use std::f64::consts::PI;
+
 use crate::{graphics::{HitRecord, Ray, textures::{SolidColor, Texture}}, math::{Color, Vec3}};
 
-use super::Material;
+use super::{Material, MaterialId};
 
 pub struct Lambertian {
     albedo: Box<dyn Texture>,
+    id: MaterialId,
 }
 
 impl Lambertian {
 
     pub fn new(texture: Box<dyn Texture>) -> Lambertian {
         Lambertian {
-            albedo: texture
+            albedo: texture,
+            id: MaterialId::new(),
         }
     }
 
     pub fn new_from_color(albedo: Color) -> Lambertian {
         Lambertian { 
-            albedo: Box::new(SolidColor::new(albedo)) 
+            albedo: Box::new(SolidColor::new(albedo)),
+            id: MaterialId::new(),
         }
     }
 }
@@ -28,8 +33,20 @@ impl Material for Lambertian {
             scatter_direction = hit.normal;
         }
         let scattered_ray = Ray::new(hit.p, scatter_direction, ray.time);
-        let attenuation = self.albedo.value(hit.u, hit.v, hit.p);
+        let attenuation = self.albedo.value_at(hit);
 
         Some((scattered_ray, attenuation))
     }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, scattered: &Ray) -> Color {
+        self.albedo.value_at(hit) * self.scattering_pdf(ray, hit, scattered)
+    }
+
+    // Offsetting the normal by a random unit vector picks cosine weighted directions
+    fn scattering_pdf(&self, _ray: &Ray, hit: &HitRecord, scattered: &Ray) -> f64 {
+        hit.normal.dot(scattered.direction).max(0.0) / PI
+    }
+    fn id(&self) -> usize {
+        self.id.get()
+    }
 }
@@ -1,8 +1,99 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
 use crate::{
     graphics::{HitRecord, Ray},
-    math::Color,
+    math::{Color, Point},
 };
 
 pub trait Material: Sync + Send {
     fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Color)>;
+
+    fn emitted(&self, _u: f64, _v: f64, _p: Point) -> Color {
+        Color::ZERO
+    }
+
+    // The light reflected back along `ray` for each unit of light arriving along `scattered`,
+    // including the cosine term. `scatter` returns this divided by `scattering_pdf`.
+    fn eval(&self, _ray: &Ray, _hit: &HitRecord, _scattered: &Ray) -> Color {
+        Color::ZERO
+    }
+
+    // The density in solid angle with which `scatter` picks the direction of `scattered`.
+    // Specular materials can't be evaluated for arbitrary directions, so they leave it at zero.
+    fn scattering_pdf(&self, _ray: &Ray, _hit: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+
+    // Phase functions scatter light inside media rather than off a surface, so light
+    // reaching them isn't spread out by the angle it arrives at
+    fn is_volumetric(&self) -> bool {
+        false
+    }
+
+    // Whether the direction `scatter` picks depends on the wavelength of the ray, so a
+    // path through it can only carry one wavelength
+    fn is_dispersive(&self) -> bool {
+        false
+    }
+
+    // Identifies the material behind any wrappers, so objects sharing one can be told apart
+    // from objects with equal but separate materials. Materials keep a `MaterialId` from
+    // when they were made to return here.
+    fn id(&self) -> usize;
+}
+
+static NEXT_MATERIAL_ID: AtomicUsize = AtomicUsize::new(0);
+
+// A number given to each material as it is made, which no other material shares. Addresses
+// can't be used instead, as materials without any fields all live at the same one.
+pub struct MaterialId(usize);
+
+impl MaterialId {
+    pub fn new() -> Self {
+        MaterialId(NEXT_MATERIAL_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub fn get(&self) -> usize {
+        self.0
+    }
+}
+
+impl Default for MaterialId {
+    fn default() -> Self {
+        MaterialId::new()
+    }
+}
+
+// Lets many objects, such as the triangles of a mesh, share a single material
+impl Material for Arc<dyn Material> {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Color)> {
+        (**self).scatter(ray, hit)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: Point) -> Color {
+        (**self).emitted(u, v, p)
+    }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, scattered: &Ray) -> Color {
+        (**self).eval(ray, hit, scattered)
+    }
+
+    fn scattering_pdf(&self, ray: &Ray, hit: &HitRecord, scattered: &Ray) -> f64 {
+        (**self).scattering_pdf(ray, hit, scattered)
+    }
+
+    fn is_volumetric(&self) -> bool {
+        (**self).is_volumetric()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        (**self).is_dispersive()
+    }
+
+    fn id(&self) -> usize {
+        (**self).id()
+    }
 }
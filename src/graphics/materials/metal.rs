@@ -3,11 +3,12 @@ use crate::{
     math::{Color, Vec3},
 };
 
-use super::Material;
+use super::{Material, MaterialId};
 
 pub struct Metal {
     albedo: Color,
     roughness: f64,
+    id: MaterialId,
 }
 
 impl Metal {
@@ -18,6 +19,7 @@ impl Metal {
         Metal {
             albedo,
             roughness: roughness.into(),
+            id: MaterialId::new(),
         }
     }
 }
@@ -36,4 +38,7 @@ impl Material for Metal {
         let attenuation = self.albedo;
         Some((scattered_ray, attenuation))
     }
+    fn id(&self) -> usize {
+        self.id.get()
+    }
 }
@@ -0,0 +1,88 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::math::{Onb, Vec3};
+
+// Below this roughness a surface is treated as perfectly smooth
+pub const SMOOTH: f64 = 1e-3;
+
+// The GGX (Trowbridge-Reitz) distribution of microfacet normals, with a roughness, or
+// alpha, along each tangent. Directions are given in a local frame with the surface
+// normal along z.
+pub struct TrowbridgeReitz {
+    pub alpha_x: f64,
+    pub alpha_y: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha_x: f64, alpha_y: f64) -> Self {
+        TrowbridgeReitz { alpha_x, alpha_y }
+    }
+
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < SMOOTH
+    }
+
+    // The density of facets facing along `h`
+    pub fn d(&self, h: Vec3) -> f64 {
+        let (ax, ay) = (self.alpha_x, self.alpha_y);
+        let d = (h.x / ax).powi(2) + (h.y / ay).powi(2) + h.z * h.z;
+        1.0 / (PI * ax * ay * d * d)
+    }
+
+    // Smith's Λ, the area of facets hidden from `w` per unit of visible area
+    fn lambda(&self, w: Vec3) -> f64 {
+        if w.z == 0.0 {
+            return f64::INFINITY;
+        }
+        let alpha2_tan2 = ((w.x * self.alpha_x).powi(2) + (w.y * self.alpha_y).powi(2)) / (w.z * w.z);
+        ((1.0 + alpha2_tan2).sqrt() - 1.0) / 2.0
+    }
+
+    pub fn g1(&self, w: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    // Height correlated masking and shadowing
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // The density with which `sample_visible_normal` picks `h` when seen from `wo`
+    pub fn visible_pdf(&self, wo: Vec3, h: Vec3) -> f64 {
+        self.g1(wo) * self.d(h) * wo.dot(h).max(0.0) / wo.z.abs()
+    }
+
+    // Picks a facet normal in proportion to how much of it `wo` sees, by Heitz's sampling
+    // of the visible normals
+    pub fn sample_visible_normal(&self, wo: Vec3) -> Vec3 {
+        let mut rng = rand::thread_rng();
+        let (u1, u2) = (rng.gen::<f64>(), rng.gen::<f64>());
+
+        // Stretch the view so the facets become a hemisphere
+        let vh = Vec3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z).normalize();
+        let length_squared = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if length_squared > 0.0 {
+            Vec3::new(-vh.y, vh.x, 0.0) / length_squared.sqrt()
+        } else {
+            Vec3::RIGHT
+        };
+        let t2 = vh.cross(t1);
+
+        // Sample the projected hemisphere, squashing the half of the disk facing away
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+
+        Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(0.0)).normalize()
+    }
+}
+
+// `v` in the frame of `onb`, the inverse of `Onb::local`
+pub fn to_local(onb: &Onb, v: Vec3) -> Vec3 {
+    Vec3::new(v.dot(onb.u), v.dot(onb.v), v.dot(onb.w))
+}
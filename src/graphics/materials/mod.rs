@@ -1,11 +1,22 @@
 mod material;
 
+mod conductor;
 mod dielectric;
+mod diffuse_light;
+mod henyey_greenstein;
+mod isotropic;
 mod lambertian;
 mod metal;
+mod microfacet;
+mod rough_dielectric;
 
-pub use material::Material;
+pub use material::{Material, MaterialId};
 
-pub use dielectric::Dielectric;
+pub use conductor::Conductor;
+pub use dielectric::{Dielectric, Dispersion};
+pub use diffuse_light::DiffuseLight;
+pub use henyey_greenstein::HenyeyGreenstein;
+pub use isotropic::Isotropic;
 pub use lambertian::Lambertian;
 pub use metal::Metal;
+pub use rough_dielectric::RoughDielectric;
@@ -0,0 +1,188 @@
+use rand::Rng;
+
+use crate::{
+    graphics::{HitRecord, Ray, textures::{SolidColor, Texture}},
+    math::{Color, Onb, Vec3},
+};
+
+use super::{Material, MaterialId, microfacet::{TrowbridgeReitz, to_local}};
+
+// Frosted glass, ice and the like, made of tiny smooth facets that each reflect or refract
+// by the exact Fresnel equations, following the microfacet model of Walter et al. The
+// roughness is the GGX alpha and can come from a texture, where its channels are averaged.
+// The tint colours light passing through the surface.
+pub struct RoughDielectric {
+    ir: f64,
+    roughness: Box<dyn Texture>,
+    tint: Color,
+    id: MaterialId,
+}
+
+// A surface seen from one side, in a frame with the normal on the side of `wo`
+struct Frame {
+    onb: Onb,
+    wo: Vec3,
+    // The index of refraction of the far side relative to the near one
+    eta: f64,
+}
+
+impl RoughDielectric {
+    pub fn new(ir: f64, roughness: Box<dyn Texture>) -> RoughDielectric {
+        RoughDielectric {
+            ir,
+            roughness,
+            tint: Color::ONE,
+            id: MaterialId::new(),
+        }
+    }
+
+    pub fn new_from_roughness(ir: f64, roughness: f64) -> RoughDielectric {
+        RoughDielectric::new(ir, Box::new(SolidColor::new(Color::ONE * roughness)))
+    }
+
+    pub fn set_tint(self, tint: Color) -> Self {
+        RoughDielectric { tint, ..self }
+    }
+
+    fn distribution(&self, hit: &HitRecord) -> TrowbridgeReitz {
+        let value = self.roughness.value_at(hit);
+        let alpha = ((value.x + value.y + value.z) / 3.0).clamp(0.0, 1.0);
+        TrowbridgeReitz::new(alpha, alpha)
+    }
+
+    // Works out which side `ray` arrives from using the outward normal, rather than the
+    // side the hit was found from, since the same hit is used for paths going both ways
+    fn frame(&self, ray: &Ray, hit: &HitRecord) -> Frame {
+        let outward = if hit.front_face { hit.normal } else { -hit.normal };
+        let (normal, eta) = if ray.direction.dot(outward) < 0.0 {
+            (outward, self.ir)
+        } else {
+            (-outward, 1.0 / self.ir)
+        };
+
+        let onb = Onb::new_from_w(normal);
+        let wo = to_local(&onb, -ray.direction);
+        Frame { onb, wo, eta }
+    }
+
+    // The facet normal that refracts `wo` into `wi`, or reflects it there, on the side of
+    // the surface normal. None for directions that can't come from any facet.
+    fn half_vector(frame: &Frame, wi: Vec3) -> Option<(Vec3, bool)> {
+        let wo = frame.wo;
+        let reflect = wo.z * wi.z > 0.0;
+        let h = if reflect { wo + wi } else { wo + wi * frame.eta };
+        if h.near_zero() {
+            return None;
+        }
+
+        let h = h.normalize();
+        let h = if h.z < 0.0 { -h } else { h };
+        if h.dot(wi) * wi.z < 0.0 || h.dot(wo) * wo.z < 0.0 {
+            return None;
+        }
+        Some((h, reflect))
+    }
+
+    // Of f cos and the density of picking `wi`, for rough surfaces
+    fn evaluate(&self, ray: &Ray, hit: &HitRecord, scattered: &Ray) -> Option<(Color, f64)> {
+        let distribution = self.distribution(hit);
+        let frame = self.frame(ray, hit);
+        let (wo, eta) = (frame.wo, frame.eta);
+        let wi = to_local(&frame.onb, scattered.direction);
+        if wo.z == 0.0 || wi.z == 0.0 {
+            return None;
+        }
+
+        let (h, reflect) = RoughDielectric::half_vector(&frame, wi)?;
+        let reflectance = fresnel_dielectric(wo.dot(h), eta);
+        let d = distribution.d(h);
+        let g = distribution.g(wo, wi);
+        let visible = distribution.visible_pdf(wo, h);
+
+        if reflect {
+            let f_cos = Color::ONE * (reflectance * d * g / (4.0 * wo.z));
+            let pdf = visible / (4.0 * wo.dot(h)) * reflectance;
+            return Some((f_cos, pdf));
+        }
+
+        let transmittance = 1.0 - reflectance;
+        let denominator = (wi.dot(h) * eta + wo.dot(h)).powi(2);
+        let jacobian = eta * eta * wi.dot(h).abs() / denominator;
+        let f_cos = self.tint * (transmittance * d * g * wo.dot(h).abs() * jacobian / wo.z);
+        let pdf = visible * jacobian * transmittance;
+        Some((f_cos, pdf))
+    }
+}
+
+// Unpolarised Fresnel reflectance into a dielectric with relative index `eta`, with the
+// cosine of the angle of incidence on the side of the normal
+fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+
+    let parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (parallel * parallel + perpendicular * perpendicular) / 2.0
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Color)> {
+        let mut rng = rand::thread_rng();
+        let distribution = self.distribution(hit);
+        let frame = self.frame(ray, hit);
+        let (wo, eta) = (frame.wo, frame.eta);
+
+        // Smooth surfaces reflect or refract about the normal itself
+        let h = if distribution.is_smooth() {
+            Vec3::FORWARD
+        } else {
+            distribution.sample_visible_normal(wo)
+        };
+
+        // Pick between reflecting and refracting by the Fresnel reflectance, which then
+        // cancels out of the weight, as does everything but the masking
+        let reflectance = fresnel_dielectric(wo.dot(h), eta);
+        let (wi, attenuation) = if rng.gen::<f64>() < reflectance {
+            let wi = (-wo).reflect(h);
+            if wi.z <= 0.0 {
+                return None;
+            }
+            (wi, Color::ONE)
+        } else {
+            let wi = (-wo).refract(h, 1.0 / eta);
+            if wi.z >= 0.0 {
+                return None;
+            }
+            (wi, self.tint)
+        };
+
+        let weight = if distribution.is_smooth() {
+            1.0
+        } else {
+            distribution.g(wo, wi) / distribution.g1(wo)
+        };
+        Some((Ray::new(hit.p, frame.onb.local(wi), ray.time), attenuation * weight))
+    }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, scattered: &Ray) -> Color {
+        if self.distribution(hit).is_smooth() {
+            return Color::ZERO;
+        }
+        self.evaluate(ray, hit, scattered).map_or(Color::ZERO, |(f_cos, _)| f_cos)
+    }
+
+    fn scattering_pdf(&self, ray: &Ray, hit: &HitRecord, scattered: &Ray) -> f64 {
+        if self.distribution(hit).is_smooth() {
+            return 0.0;
+        }
+        self.evaluate(ray, hit, scattered).map_or(0.0, |(_, pdf)| pdf)
+    }
+
+    fn id(&self) -> usize {
+        self.id.get()
+    }
+}
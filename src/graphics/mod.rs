@@ -3,14 +3,21 @@ mod hittable;
 mod ray;
 mod aabb;
 mod bvh;
+mod instance;
+mod transform;
 
+pub mod backgrounds;
 pub mod materials;
 pub mod models;
 pub mod textures;
+pub mod volumes;
 
 pub use camera::Camera;
+pub use hittable::Barycentrics;
 pub use hittable::HitRecord;
 pub use hittable::Hittable;
 pub use ray::Ray;
 pub use aabb::Aabb;
 pub use bvh::Bvh;
+pub use instance::Instance;
+pub use transform::Transform;
@@ -0,0 +1,250 @@
+use rand::Rng;
+
+use crate::{graphics::materials::Material, math::{Axis, Point, Vec3}};
+
+use crate::graphics::{Aabb, HitRecord, Hittable, Ray};
+
+// Rectangles have no thickness, so their bounding boxes are padded along the normal
+const BOX_PADDING: f64 = 0.0001;
+
+// The geometry shared by every axis aligned rectangle. The rectangle lies in the
+// plane where the `k_axis` coordinate equals `k`, spanning [a0, a1] x [b0, b1]
+// in the two remaining axes, taken in x, y, z order.
+pub(super) struct AxisRect {
+    k_axis: Axis,
+    a0: f64,
+    a1: f64,
+    b0: f64,
+    b1: f64,
+    k: f64,
+    outward_normal: Vec3,
+}
+
+pub(super) struct RectHit {
+    pub t: f64,
+    pub p: Point,
+    pub outward_normal: Vec3,
+    pub tangent: Vec3,
+    pub u: f64,
+    pub v: f64,
+}
+
+impl AxisRect {
+    // The extents can be given in either order
+    pub fn new(k_axis: Axis, a0: f64, a1: f64, b0: f64, b1: f64, k: f64, flip_normal: bool) -> Self {
+        let normal = AxisRect::compose(k_axis, 0.0, 0.0, 1.0);
+        AxisRect {
+            k_axis,
+            a0: a0.min(a1),
+            a1: a0.max(a1),
+            b0: b0.min(b1),
+            b1: b0.max(b1),
+            k,
+            outward_normal: if flip_normal { -normal } else { normal },
+        }
+    }
+
+    fn axes(k_axis: Axis) -> (Axis, Axis) {
+        match k_axis {
+            Axis::X => (Axis::Y, Axis::Z),
+            Axis::Y => (Axis::X, Axis::Z),
+            Axis::Z => (Axis::X, Axis::Y),
+        }
+    }
+
+    // Builds a point from its coordinates along the a, b and k axes
+    fn compose(k_axis: Axis, a: f64, b: f64, k: f64) -> Point {
+        match k_axis {
+            Axis::X => Point::new(k, a, b),
+            Axis::Y => Point::new(a, k, b),
+            Axis::Z => Point::new(a, b, k),
+        }
+    }
+
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<RectHit> {
+        let (a_axis, b_axis) = AxisRect::axes(self.k_axis);
+
+        let t = (self.k - ray.origin[&self.k_axis]) / ray.direction[&self.k_axis];
+        if !(t_min..=t_max).contains(&t) {
+            return None;
+        }
+
+        let a = ray.origin[&a_axis] + t * ray.direction[&a_axis];
+        let b = ray.origin[&b_axis] + t * ray.direction[&b_axis];
+        if a < self.a0 || a > self.a1 || b < self.b0 || b > self.b1 {
+            return None;
+        }
+
+        Some(RectHit {
+            t,
+            p: ray.at(t),
+            outward_normal: self.outward_normal,
+            tangent: AxisRect::compose(self.k_axis, 1.0, 0.0, 0.0),
+            u: (a - self.a0) / (self.a1 - self.a0),
+            v: (b - self.b0) / (self.b1 - self.b0),
+        })
+    }
+
+    // Points are picked uniformly over the area, then converted to a density in solid angle
+    pub fn pdf_value(&self, origin: Point, direction: Vec3) -> f64 {
+        let hit = match self.hit(&Ray::new(origin, direction, 0.0), 0.001, f64::INFINITY) {
+            Some(h) => h,
+            None => return 0.0,
+        };
+
+        let area = self.area();
+        let cosine = direction.normalize().dot(self.outward_normal).abs();
+        if cosine == 0.0 {
+            return 0.0;
+        }
+
+        hit.t * hit.t / (cosine * area)
+    }
+
+    pub fn random(&self, origin: Point) -> Vec3 {
+        self.sample().p - origin
+    }
+
+    pub fn area(&self) -> f64 {
+        (self.a1 - self.a0) * (self.b1 - self.b0)
+    }
+
+    // A point picked uniformly over the rectangle, which may have no width
+    pub fn sample(&self) -> RectHit {
+        let mut rng = rand::thread_rng();
+        let (u, v) = (rng.gen::<f64>(), rng.gen::<f64>());
+        let a = self.a0 + u * (self.a1 - self.a0);
+        let b = self.b0 + v * (self.b1 - self.b0);
+
+        RectHit {
+            t: 0.0,
+            p: AxisRect::compose(self.k_axis, a, b, self.k),
+            outward_normal: self.outward_normal,
+            tangent: AxisRect::compose(self.k_axis, 1.0, 0.0, 0.0),
+            u,
+            v,
+        }
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        Aabb::new(
+            AxisRect::compose(self.k_axis, self.a0, self.b0, self.k - BOX_PADDING),
+            AxisRect::compose(self.k_axis, self.a1, self.b1, self.k + BOX_PADDING),
+        )
+    }
+}
+
+macro_rules! impl_axis_rect {
+    ($Rect:ident $k_axis:expr) => {
+        pub struct $Rect {
+            rect: AxisRect,
+            pub material: Box<dyn Material>,
+        }
+
+        impl $Rect {
+            pub fn new(a0: f64, a1: f64, b0: f64, b1: f64, k: f64, material: Box<dyn Material>) -> $Rect {
+                $Rect {
+                    rect: AxisRect::new($k_axis, a0, a1, b0, b1, k, false),
+                    material,
+                }
+            }
+        }
+
+        impl Hittable for $Rect {
+            fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+                let hit = self.rect.hit(ray, t_min, t_max)?;
+
+                let mut record = HitRecord::new(
+                    hit.p,
+                    hit.outward_normal,
+                    hit.t,
+                    hit.u,
+                    hit.v,
+                    ray,
+                    &*self.material,
+                );
+                record.tangent = Some(hit.tangent);
+
+                Some(record)
+            }
+
+            fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+                Some(self.rect.bounding_box())
+            }
+
+            fn pdf_value(&self, origin: Point, direction: Vec3) -> f64 {
+                self.rect.pdf_value(origin, direction)
+            }
+
+            fn random(&self, origin: Point) -> Vec3 {
+                self.rect.random(origin)
+            }
+
+            fn area(&self) -> f64 {
+                self.rect.area()
+            }
+
+            fn sample_surface(&self) -> Option<HitRecord<'_>> {
+                let hit = self.rect.sample();
+                let ray = Ray::new(hit.p + hit.outward_normal, -hit.outward_normal, 0.0);
+
+                let mut record = HitRecord::new(
+                    hit.p,
+                    hit.outward_normal,
+                    hit.t,
+                    hit.u,
+                    hit.v,
+                    &ray,
+                    &*self.material,
+                );
+                record.tangent = Some(hit.tangent);
+
+                Some(record)
+            }
+        }
+    };
+}
+
+impl_axis_rect!(XyRect Axis::Z);
+impl_axis_rect!(XzRect Axis::Y);
+impl_axis_rect!(YzRect Axis::X);
+
+#[cfg(test)]
+mod tests {
+    use crate::{graphics::materials::Lambertian, math::Color};
+
+    use super::*;
+
+    fn material() -> Box<dyn Material> {
+        Box::new(Lambertian::new_from_color(Color::ONE))
+    }
+
+    #[test]
+    fn reversed_extents() {
+        let rect = XzRect::new(1.0, -1.0, 3.0, 1.0, 0.5, material());
+        assert!((rect.area() - 4.0).abs() < 1e-12);
+
+        let ray = Ray::new(Point::new(0.5, 2.0, 2.5), -Vec3::UP, 0.0);
+        let hit = rect.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((hit.t - 1.5).abs() < 1e-12);
+        assert!((hit.u - 0.75).abs() < 1e-12 && (hit.v - 0.75).abs() < 1e-12);
+
+        let bounds = rect.bounding_box(0.0, 1.0).unwrap();
+        assert!(bounds.minimum.x == -1.0 && bounds.maximum.z == 3.0);
+
+        for _ in 0..100 {
+            let p = rect.sample_surface().unwrap().p;
+            assert!((-1.0..=1.0).contains(&p.x) && (1.0..=3.0).contains(&p.z));
+        }
+    }
+
+    #[test]
+    fn zero_width_can_be_sampled() {
+        let rect = XyRect::new(0.0, 0.0, 0.0, 1.0, 0.0, material());
+        assert_eq!(rect.area(), 0.0);
+
+        let hit = rect.sample_surface().unwrap();
+        assert_eq!(hit.p.x, 0.0);
+        assert!(rect.random(Point::new(0.0, 0.5, 1.0)).x == 0.0);
+    }
+}
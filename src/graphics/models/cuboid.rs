@@ -0,0 +1,94 @@
+use crate::{graphics::materials::Material, math::{Axis, Point}};
+
+use crate::graphics::{Aabb, HitRecord, Hittable, Ray};
+
+use super::axis_rect::AxisRect;
+
+// An axis aligned box, made of six rectangles with outward facing normals
+pub struct Cuboid {
+    minimum: Point,
+    maximum: Point,
+    sides: [AxisRect; 6],
+    pub material: Box<dyn Material>,
+}
+
+impl Cuboid {
+    // The corners can be any two opposite ones
+    pub fn new(p0: Point, p1: Point, material: Box<dyn Material>) -> Cuboid {
+        let (p0, p1) = (
+            Point::new(p0.x.min(p1.x), p0.y.min(p1.y), p0.z.min(p1.z)),
+            Point::new(p0.x.max(p1.x), p0.y.max(p1.y), p0.z.max(p1.z)),
+        );
+
+        let sides = [
+            AxisRect::new(Axis::Z, p0.x, p1.x, p0.y, p1.y, p1.z, false),
+            AxisRect::new(Axis::Z, p0.x, p1.x, p0.y, p1.y, p0.z, true),
+            AxisRect::new(Axis::Y, p0.x, p1.x, p0.z, p1.z, p1.y, false),
+            AxisRect::new(Axis::Y, p0.x, p1.x, p0.z, p1.z, p0.y, true),
+            AxisRect::new(Axis::X, p0.y, p1.y, p0.z, p1.z, p1.x, false),
+            AxisRect::new(Axis::X, p0.y, p1.y, p0.z, p1.z, p0.x, true),
+        ];
+
+        Cuboid {
+            minimum: p0,
+            maximum: p1,
+            sides,
+            material,
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut closest_hit = None;
+        let mut closest_so_far = t_max;
+
+        for side in self.sides.iter() {
+            if let Some(hit) = side.hit(ray, t_min, closest_so_far) {
+                closest_so_far = hit.t;
+                closest_hit = Some(hit);
+            }
+        }
+
+        closest_hit.map(|hit| {
+            let mut record = HitRecord::new(
+                hit.p,
+                hit.outward_normal,
+                hit.t,
+                hit.u,
+                hit.v,
+                ray,
+                &*self.material,
+            );
+            record.tangent = Some(hit.tangent);
+            record
+        })
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        Some(Aabb::new(self.minimum, self.maximum))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{graphics::materials::Lambertian, math::{Color, Vec3}};
+
+    use super::*;
+
+    #[test]
+    fn corners_in_any_order() {
+        let material = Box::new(Lambertian::new_from_color(Color::ONE));
+        let cuboid = Cuboid::new(Point::new(1.0, 0.0, 1.0), Point::new(0.0, 1.0, 0.0), material);
+
+        let bounds = cuboid.bounding_box(0.0, 1.0).unwrap();
+        assert!((bounds.minimum - Vec3::ZERO).near_zero());
+        assert!((bounds.maximum - Vec3::ONE).near_zero());
+
+        let ray = Ray::new(Point::new(0.5, 0.5, 3.0), -Vec3::FORWARD, 0.0);
+        let hit = cuboid.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((hit.t - 2.0).abs() < 1e-12);
+        assert!(hit.front_face);
+        assert!((hit.normal - Vec3::FORWARD).near_zero());
+    }
+}
@@ -1,5 +1,13 @@
+mod axis_rect;
+mod cuboid;
 mod sphere;
 mod moving_sphere;
+mod triangle;
+mod triangle_mesh;
 
+pub use axis_rect::{XyRect, XzRect, YzRect};
+pub use cuboid::Cuboid;
 pub use sphere::Sphere;
 pub use moving_sphere::MovingSphere;
+pub use triangle::Triangle;
+pub use triangle_mesh::TriangleMesh;
@@ -31,7 +31,7 @@ impl MovingSphere {
 }
 
 impl Hittable for MovingSphere {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         let oc = ray.origin - self.center(ray.time);
          // Quadratic formula
          let b = oc.dot(ray.direction);
@@ -55,7 +55,7 @@ impl Hittable for MovingSphere {
          let intersection_point = ray.at(root);
          let normal = (intersection_point - self.center(ray.time)) / self.radius;
          let (u, v) = Sphere::get_uv(normal);
-         Some(HitRecord::new(
+         let mut record = HitRecord::new(
              intersection_point,
              normal,
              root,
@@ -63,7 +63,10 @@ impl Hittable for MovingSphere {
              v,
              ray,
              &*self.material,
-         ))
+         );
+         record.tangent = Sphere::get_tangent(normal);
+
+         Some(record)
     }
 
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
@@ -1,6 +1,8 @@
 use std::f64::consts::PI;
 
-use crate::{graphics::materials::Material, math::{Point, Vec3}};
+use rand::Rng;
+
+use crate::{graphics::materials::Material, math::{Onb, Point, Vec3}};
 
 use crate::graphics::{Aabb, HitRecord, Hittable, Ray};
 pub struct Sphere {
@@ -24,10 +26,20 @@ impl Sphere {
 
         (phi / (2.0 * PI), theta / PI)
     }
+
+    // The direction u increases in around the sphere, which is undefined at the poles
+    pub fn get_tangent(p: Point) -> Option<Vec3> {
+        let tangent = Vec3::new(p.z, 0.0, -p.x);
+        if tangent.near_zero() {
+            None
+        } else {
+            Some(tangent.normalize())
+        }
+    }
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         let oc = ray.origin - self.center;
         // Quadratic formula
         let b = oc.dot(ray.direction);
@@ -52,7 +64,7 @@ impl Hittable for Sphere {
         let normal = (intersection_point - self.center) / self.radius;
         let (u, v) = Sphere::get_uv(normal);
 
-        Some(HitRecord::new(
+        let mut record = HitRecord::new(
             intersection_point,
             normal,
             root,
@@ -60,7 +72,10 @@ impl Hittable for Sphere {
             v,
             ray,
             &*self.material,
-        ))
+        );
+        record.tangent = Sphere::get_tangent(normal);
+
+        Some(record)
     }
 
     fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
@@ -70,4 +85,76 @@ impl Hittable for Sphere {
             self.center + Vec3::ONE * self.radius)
         )
     }
+
+    // Directions are sampled uniformly over the cone the sphere fills as seen from the origin
+    fn pdf_value(&self, origin: Point, direction: Vec3) -> f64 {
+        if self.hit(&Ray::new(origin, direction, 0.0), 0.001, f64::INFINITY).is_none() {
+            return 0.0;
+        }
+
+        let distance_squared = (self.center - origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return 1.0 / (4.0 * PI);
+        }
+
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+    }
+
+    fn random(&self, origin: Point) -> Vec3 {
+        let direction = self.center - origin;
+        let distance_squared = direction.length_squared();
+        let radius_squared = self.radius * self.radius;
+
+        // From inside, the sphere surrounds the origin in every direction
+        if distance_squared <= radius_squared {
+            return Vec3::random_unit_vector();
+        }
+
+        let mut rng = rand::thread_rng();
+        let (r1, r2) = (rng.gen::<f64>(), rng.gen::<f64>());
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+        let phi = 2.0 * PI * r1;
+        let sin_theta = (1.0 - z * z).max(0.0).sqrt();
+
+        Onb::new_from_w(direction).local(Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z))
+    }
+
+    fn area(&self) -> f64 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord<'_>> {
+        let normal = Vec3::random_unit_vector();
+        let point = self.center + normal * self.radius;
+        let (u, v) = Sphere::get_uv(normal);
+
+        let ray = Ray::new(point + normal, -normal, 0.0);
+        let mut record = HitRecord::new(point, normal, 0.0, u, v, &ray, &*self.material);
+        record.tangent = Sphere::get_tangent(normal);
+
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tangent_points_towards_increasing_u() {
+        for &p in [Vec3::RIGHT, Vec3::FORWARD, Vec3::new(-1.0, 0.5, -0.3).normalize()].iter() {
+            let tangent = Sphere::get_tangent(p).unwrap();
+            assert!(tangent.dot(p).abs() < 1e-12);
+
+            let (u, v) = Sphere::get_uv(p);
+            let (stepped_u, stepped_v) = Sphere::get_uv((p + 1e-6 * tangent).normalize());
+            assert!(stepped_u > u && (stepped_v - v).abs() < 1e-9);
+        }
+
+        assert!(Sphere::get_tangent(Vec3::UP).is_none());
+    }
 }
@@ -0,0 +1,184 @@
+use crate::{graphics::materials::Material, math::{Point, Vec3}};
+
+use crate::graphics::{Aabb, HitRecord, Hittable, Ray};
+
+const EPSILON: f64 = 1e-12;
+
+// Triangles lying in an axis aligned plane are flat, so their boxes are padded
+const BOX_PADDING: f64 = 0.0001;
+
+pub struct Triangle {
+    vertices: [Point; 3],
+    normals: Option<[Vec3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    pub material: Box<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(v0: Point, v1: Point, v2: Point, material: Box<dyn Material>) -> Triangle {
+        Triangle {
+            vertices: [v0, v1, v2],
+            normals: None,
+            uvs: None,
+            material,
+        }
+    }
+
+    // Per vertex normals, interpolated across the face for smooth shading
+    pub fn set_normals(self, normals: [Vec3; 3]) -> Self {
+        Triangle {
+            normals: Some([normals[0].normalize(), normals[1].normalize(), normals[2].normalize()]),
+            ..self
+        }
+    }
+
+    pub fn set_uvs(self, uvs: [(f64, f64); 3]) -> Self {
+        Triangle {
+            uvs: Some(uvs),
+            ..self
+        }
+    }
+
+    // Möller–Trumbore ray triangle intersection, returning the distance along the ray
+    // and the barycentric coordinates of the second and third vertices
+    pub fn intersect(vertices: [Point; 3], ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64, f64)> {
+        let edge1 = vertices[1] - vertices[0];
+        let edge2 = vertices[2] - vertices[0];
+
+        let p = ray.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() < EPSILON {
+            return None;
+        }
+        let inv_determinant = 1.0 / determinant;
+
+        let s = ray.origin - vertices[0];
+        let b1 = s.dot(p) * inv_determinant;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+
+        let q = s.cross(edge1);
+        let b2 = ray.direction.dot(q) * inv_determinant;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(q) * inv_determinant;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        Some((t, b1, b2))
+    }
+
+    // Interpolates a shading normal, kept on the same side as the geometric normal
+    pub fn shading_normal(geometric_normal: Vec3, normals: [Vec3; 3], b1: f64, b2: f64) -> Vec3 {
+        let b0 = 1.0 - b1 - b2;
+        let normal = (b0 * normals[0] + b1 * normals[1] + b2 * normals[2]).normalize();
+
+        if normal.dot(geometric_normal) < 0.0 {
+            -normal
+        } else {
+            normal
+        }
+    }
+
+    pub fn interpolate_uv(uvs: [(f64, f64); 3], b1: f64, b2: f64) -> (f64, f64) {
+        let b0 = 1.0 - b1 - b2;
+        (
+            b0 * uvs[0].0 + b1 * uvs[1].0 + b2 * uvs[2].0,
+            b0 * uvs[0].1 + b1 * uvs[1].1 + b2 * uvs[2].1,
+        )
+    }
+
+    // The direction u increases in across the face, from how the texture coordinates
+    // change along its edges. Without them u is the weight of the second vertex.
+    pub fn tangent(vertices: [Point; 3], uvs: Option<[(f64, f64); 3]>) -> Option<Vec3> {
+        let edge1 = vertices[1] - vertices[0];
+        let edge2 = vertices[2] - vertices[0];
+        let [uv0, uv1, uv2] = match uvs {
+            Some(uvs) => uvs,
+            None => return Some(edge1.normalize()),
+        };
+
+        let (du1, dv1) = (uv1.0 - uv0.0, uv1.1 - uv0.1);
+        let (du2, dv2) = (uv2.0 - uv0.0, uv2.1 - uv0.1);
+        let determinant = du1 * dv2 - du2 * dv1;
+        if determinant.abs() < EPSILON {
+            return None;
+        }
+
+        let tangent = (dv2 * edge1 - dv1 * edge2) / determinant;
+        if tangent.length_squared() == 0.0 {
+            None
+        } else {
+            Some(tangent.normalize())
+        }
+    }
+
+    pub fn bounds(vertices: [Point; 3]) -> Aabb {
+        let [a, b, c] = vertices;
+        let padding = Vec3::ONE * BOX_PADDING;
+
+        Aabb::new(
+            Point::new(a.x.min(b.x).min(c.x), a.y.min(b.y).min(c.y), a.z.min(b.z).min(c.z)) - padding,
+            Point::new(a.x.max(b.x).max(c.x), a.y.max(b.y).max(c.y), a.z.max(b.z).max(c.z)) + padding,
+        )
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (t, b1, b2) = Triangle::intersect(self.vertices, ray, t_min, t_max)?;
+
+        let geometric_normal = (self.vertices[1] - self.vertices[0])
+            .cross(self.vertices[2] - self.vertices[0])
+            .normalize();
+        let normal = match self.normals {
+            Some(normals) => Triangle::shading_normal(geometric_normal, normals, b1, b2),
+            None => geometric_normal,
+        };
+        let (u, v) = match self.uvs {
+            Some(uvs) => Triangle::interpolate_uv(uvs, b1, b2),
+            None => (b1, b2),
+        };
+
+        let mut record = HitRecord::new(
+            ray.at(t),
+            normal,
+            t,
+            u,
+            v,
+            ray,
+            &*self.material,
+        );
+        record.tangent = Triangle::tangent(self.vertices, self.uvs);
+
+        Some(record)
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        Some(Triangle::bounds(self.vertices))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tangent_follows_increasing_u() {
+        let vertices = [Point::ZERO, Point::new(2.0, 0.0, 0.0), Point::new(0.0, 0.0, 3.0)];
+
+        let tangent = Triangle::tangent(vertices, None).unwrap();
+        assert!((tangent - Vec3::RIGHT).near_zero());
+
+        // Texture coordinates turned a quarter turn, so u runs along the second edge
+        let uvs = [(0.0, 0.0), (0.0, -1.0), (1.0, 0.0)];
+        let tangent = Triangle::tangent(vertices, Some(uvs)).unwrap();
+        assert!((tangent - Vec3::FORWARD).near_zero());
+
+        assert!(Triangle::tangent(vertices, Some([(0.5, 0.5); 3])).is_none());
+    }
+}
@@ -0,0 +1,369 @@
+use crate::{graphics::materials::Material, math::{Axis, Point, Vec3}};
+
+use crate::graphics::{Aabb, Barycentrics, HitRecord, Hittable, Ray};
+
+use super::Triangle;
+
+const MAX_LEAF_TRIANGLES: usize = 4;
+const SAH_BINS: usize = 12;
+const MAX_STACK_DEPTH: usize = 64;
+
+// Past this depth nodes are split at the median, which halves them each level, so skewed
+// meshes where the surface area heuristic only peels off a few triangles at a time still
+// fit in the traversal stack
+const MEDIAN_SPLIT_DEPTH: usize = MAX_STACK_DEPTH / 2;
+
+// A node of the mesh's flattened BVH. Interior nodes have no triangles; their left child
+// directly follows them and `offset` is the index of the right child. Leaves cover
+// `count` triangles starting at `offset`.
+struct MeshNode {
+    bounds: Aabb,
+    offset: u32,
+    count: u32,
+    axis: Axis,
+}
+
+// Triangles sharing indexed vertex data and a single material, with their own BVH
+pub struct TriangleMesh {
+    positions: Vec<Point>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(f64, f64)>>,
+    indices: Vec<[u32; 3]>,
+    nodes: Vec<MeshNode>,
+    pub material: Box<dyn Material>,
+}
+
+impl TriangleMesh {
+    pub fn new(positions: Vec<Point>, indices: Vec<[u32; 3]>, material: Box<dyn Material>) -> TriangleMesh {
+        if indices.is_empty() {
+            panic!("No triangles passed into TriangleMesh");
+        }
+
+        let mut mesh = TriangleMesh {
+            positions,
+            normals: None,
+            uvs: None,
+            indices,
+            nodes: Vec::new(),
+            material,
+        };
+        mesh.build();
+        mesh
+    }
+
+    // Per vertex normals, indexed the same way as the positions
+    pub fn set_normals(self, normals: Vec<Vec3>) -> Self {
+        if normals.len() != self.positions.len() {
+            panic!("{} normals passed into TriangleMesh with {} positions", normals.len(), self.positions.len());
+        }
+
+        TriangleMesh {
+            normals: Some(normals.iter().map(|n| n.normalize()).collect()),
+            ..self
+        }
+    }
+
+    // Per vertex texture coordinates, indexed the same way as the positions
+    pub fn set_uvs(self, uvs: Vec<(f64, f64)>) -> Self {
+        if uvs.len() != self.positions.len() {
+            panic!("{} uvs passed into TriangleMesh with {} positions", uvs.len(), self.positions.len());
+        }
+
+        TriangleMesh {
+            uvs: Some(uvs),
+            ..self
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn vertices(&self, triangle: usize) -> [Point; 3] {
+        let [a, b, c] = self.indices[triangle];
+        [self.positions[a as usize], self.positions[b as usize], self.positions[c as usize]]
+    }
+
+    fn build(&mut self) {
+        let bounds: Vec<Aabb> = (0..self.indices.len()).map(|i| Triangle::bounds(self.vertices(i))).collect();
+        let centroids: Vec<Point> = bounds.iter().map(|b| (b.minimum + b.maximum) * 0.5).collect();
+        let mut order: Vec<usize> = (0..self.indices.len()).collect();
+
+        self.nodes = Vec::with_capacity(2 * self.indices.len() / MAX_LEAF_TRIANGLES + 1);
+        TriangleMesh::build_node(&mut self.nodes, &mut order, 0, 0, &bounds, &centroids);
+
+        self.indices = order.iter().map(|&i| self.indices[i]).collect();
+    }
+
+    fn axis_value(p: Point, axis: Axis) -> f64 {
+        p[&axis]
+    }
+
+    fn surface_area(b: &Aabb) -> f64 {
+        let d = b.maximum - b.minimum;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    fn build_node(
+        nodes: &mut Vec<MeshNode>,
+        order: &mut [usize],
+        start: usize,
+        depth: usize,
+        bounds: &[Aabb],
+        centroids: &[Point],
+    ) {
+        let node_bounds = order[1..]
+            .iter()
+            .fold(bounds[order[0]], |b, &i| b.surrounding_box(&bounds[i]));
+
+        let node_index = nodes.len();
+        nodes.push(MeshNode {
+            bounds: node_bounds,
+            offset: start as u32,
+            count: order.len() as u32,
+            axis: Axis::X,
+        });
+
+        // Traversing an interior node pushes both children, one deeper in the stack than
+        // the node itself, so the deepest nodes have to be leaves however many triangles
+        // they hold
+        if order.len() <= MAX_LEAF_TRIANGLES || depth + 2 >= MAX_STACK_DEPTH {
+            return;
+        }
+
+        let mut centroid_min = centroids[order[0]];
+        let mut centroid_max = centroids[order[0]];
+        for &i in order.iter() {
+            let c = centroids[i];
+            centroid_min = Point::new(centroid_min.x.min(c.x), centroid_min.y.min(c.y), centroid_min.z.min(c.z));
+            centroid_max = Point::new(centroid_max.x.max(c.x), centroid_max.y.max(c.y), centroid_max.z.max(c.z));
+        }
+
+        let extent = centroid_max - centroid_min;
+        let axis = if extent.x > extent.y && extent.x > extent.z {
+            Axis::X
+        } else if extent.y > extent.z {
+            Axis::Y
+        } else {
+            Axis::Z
+        };
+
+        // Every centroid is in the same place, so there is no useful split
+        let axis_min = TriangleMesh::axis_value(centroid_min, axis);
+        let axis_extent = TriangleMesh::axis_value(extent, axis);
+        if axis_extent <= 0.0 {
+            return;
+        }
+
+        let bin_of = |i: usize| {
+            let offset = (TriangleMesh::axis_value(centroids[i], axis) - axis_min) / axis_extent;
+            ((offset * SAH_BINS as f64) as usize).min(SAH_BINS - 1)
+        };
+
+        // Bin the triangles along the axis and pick the split with the lowest surface area cost
+        let mut bin_counts = [0usize; SAH_BINS];
+        let mut bin_bounds: [Option<Aabb>; SAH_BINS] = [None; SAH_BINS];
+        for &i in order.iter() {
+            let bin = bin_of(i);
+            bin_counts[bin] += 1;
+            bin_bounds[bin] = Some(match bin_bounds[bin] {
+                Some(b) => b.surrounding_box(&bounds[i]),
+                None => bounds[i],
+            });
+        }
+
+        let mut best_split = SAH_BINS / 2;
+        let mut best_cost = f64::INFINITY;
+        for split in 1..SAH_BINS {
+            let side_cost = |range: std::ops::Range<usize>| {
+                let mut count = 0;
+                let mut side_bounds: Option<Aabb> = None;
+                for bin in range {
+                    count += bin_counts[bin];
+                    if let Some(b) = bin_bounds[bin] {
+                        side_bounds = Some(side_bounds.map_or(b, |s| s.surrounding_box(&b)));
+                    }
+                }
+                side_bounds.map_or(0.0, |b| count as f64 * TriangleMesh::surface_area(&b))
+            };
+
+            let cost = side_cost(0..split) + side_cost(split..SAH_BINS);
+            if cost < best_cost {
+                best_cost = cost;
+                best_split = split;
+            }
+        }
+
+        let mut mid = 0;
+        if depth < MEDIAN_SPLIT_DEPTH {
+            for i in 0..order.len() {
+                if bin_of(order[i]) < best_split {
+                    order.swap(i, mid);
+                    mid += 1;
+                }
+            }
+        }
+
+        // Fall back to a median split when the binning puts everything on one side, or the
+        // tree is getting deep
+        if mid == 0 || mid == order.len() {
+            mid = order.len() / 2;
+            order.select_nth_unstable_by(mid, |&a, &b| {
+                TriangleMesh::axis_value(centroids[a], axis).total_cmp(&TriangleMesh::axis_value(centroids[b], axis))
+            });
+        }
+
+        let (left, right) = order.split_at_mut(mid);
+        TriangleMesh::build_node(nodes, left, start, depth + 1, bounds, centroids);
+        let right_index = nodes.len();
+        TriangleMesh::build_node(nodes, right, start + mid, depth + 1, bounds, centroids);
+
+        let node = &mut nodes[node_index];
+        node.offset = right_index as u32;
+        node.count = 0;
+        node.axis = axis;
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut closest_so_far = t_max;
+        let mut closest_hit: Option<(usize, f64, f64, f64)> = None;
+
+        let mut stack = [0usize; MAX_STACK_DEPTH];
+        let mut stack_size = 1;
+
+        while stack_size > 0 {
+            stack_size -= 1;
+            let node = &self.nodes[stack[stack_size]];
+
+            if !node.bounds.hit(ray, t_min, closest_so_far) {
+                continue;
+            }
+
+            if node.count > 0 {
+                let first = node.offset as usize;
+                for triangle in first..first + node.count as usize {
+                    if let Some((t, b1, b2)) = Triangle::intersect(self.vertices(triangle), ray, t_min, closest_so_far) {
+                        closest_so_far = t;
+                        closest_hit = Some((triangle, t, b1, b2));
+                    }
+                }
+            } else {
+                // Visit the nearer child first by pushing it last
+                let left = stack[stack_size] + 1;
+                let right = node.offset as usize;
+                let axis_index = match node.axis {
+                    Axis::X => 0,
+                    Axis::Y => 1,
+                    Axis::Z => 2,
+                };
+                let (near, far) = if ray.sign[axis_index] == 1 { (right, left) } else { (left, right) };
+
+                stack[stack_size] = far;
+                stack[stack_size + 1] = near;
+                stack_size += 2;
+            }
+        }
+
+        let (triangle, t, b1, b2) = closest_hit?;
+        let vertices = self.vertices(triangle);
+        let [a, b, c] = self.indices[triangle];
+
+        let geometric_normal = (vertices[1] - vertices[0]).cross(vertices[2] - vertices[0]).normalize();
+        let normal = match &self.normals {
+            Some(normals) => Triangle::shading_normal(
+                geometric_normal,
+                [normals[a as usize], normals[b as usize], normals[c as usize]],
+                b1,
+                b2,
+            ),
+            None => geometric_normal,
+        };
+        let corner_uvs = self.uvs.as_ref().map(|uvs| [uvs[a as usize], uvs[b as usize], uvs[c as usize]]);
+        let (u, v) = match corner_uvs {
+            Some(uvs) => Triangle::interpolate_uv(uvs, b1, b2),
+            None => (b1, b2),
+        };
+
+        let mut record = HitRecord::new(
+            ray.at(t),
+            normal,
+            t,
+            u,
+            v,
+            ray,
+            &*self.material,
+        );
+        record.barycentrics = Some(Barycentrics {
+            vertices: [a as usize, b as usize, c as usize],
+            weights: [1.0 - b1 - b2, b1, b2],
+        });
+        record.tangent = Triangle::tangent(vertices, corner_uvs);
+
+        Some(record)
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        Some(self.nodes[0].bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graphics::materials::Lambertian, math::Color};
+
+    // Triangles facing along z at the given x positions, sized in proportion to them
+    fn strip(xs: &[f64]) -> TriangleMesh {
+        let mut positions = Vec::new();
+        let mut indices = Vec::new();
+        for (i, &x) in xs.iter().enumerate() {
+            let size = 0.01 * x.abs().max(1.0);
+            positions.push(Point::new(x - size, -size, 0.0));
+            positions.push(Point::new(x + size, -size, 0.0));
+            positions.push(Point::new(x, size, 0.0));
+            let first = 3 * i as u32;
+            indices.push([first, first + 1, first + 2]);
+        }
+        TriangleMesh::new(positions, indices, Box::new(Lambertian::new_from_color(Color::ONE)))
+    }
+
+    fn skewed_positions() -> Vec<f64> {
+        (0..1000).map(|i| 1.2f64.powi(i)).collect()
+    }
+
+    fn assert_hits(mesh: &TriangleMesh, xs: &[f64]) {
+        for &x in xs.iter().filter(|x| !x.is_nan()).step_by(37) {
+            let ray = Ray::new(Point::new(x, 0.0, -1.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+            let hit = mesh.hit(&ray, 0.001, f64::INFINITY).unwrap_or_else(|| panic!("missed the triangle at {}", x));
+            assert!((hit.p.x - x).abs() < 1e-9 * x);
+        }
+    }
+
+    #[test]
+    fn skewed_centroids_keep_the_tree_shallow_enough_to_traverse() {
+        let xs = skewed_positions();
+        assert_hits(&strip(&xs), &xs);
+    }
+
+    #[test]
+    fn nan_centroids_do_not_panic() {
+        let mut xs = skewed_positions();
+        xs[500] = f64::NAN;
+        xs[900] = f64::NAN;
+        assert_hits(&strip(&xs), &xs);
+    }
+
+    #[test]
+    #[should_panic(expected = "2 normals passed into TriangleMesh with 3 positions")]
+    fn short_normals_are_rejected() {
+        strip(&[0.0]).set_normals(vec![Vec3::FORWARD; 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "4 uvs passed into TriangleMesh with 3 positions")]
+    fn long_uvs_are_rejected() {
+        strip(&[0.0]).set_uvs(vec![(0.0, 0.0); 4]);
+    }
+}
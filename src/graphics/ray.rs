@@ -3,12 +3,16 @@ pub struct Ray {
     pub origin: Point,
     pub direction: Vec3,
     pub time: f64,
+    // The wavelength in nanometres carried by rays from spectral integrators, which
+    // dispersive materials bend by. RGB integrators leave it unset.
+    pub wavelength: Option<f64>,
     pub inv_direction: Vec3,
     pub sign: [usize; 3],
 }
 
 impl Ray {
     pub fn new(origin: Point, direction: Vec3, time: f64) -> Self {
+        let direction = direction.normalize();
         let inv_direction = Vec3::new(
             1.0 / direction.x,
             1.0 / direction.y,
@@ -23,8 +27,9 @@ impl Ray {
 
         Ray { 
             origin, 
-            direction: direction.normalize(), 
+            direction,
             time,
+            wavelength: None,
             inv_direction,
             sign,
         }
@@ -3,6 +3,7 @@ mod solid_color;
 mod checker_texture;
 mod perlin_texture;
 mod image_texture;
+mod vertex_color_texture;
 
 mod utils;
 
@@ -11,3 +12,6 @@ pub use solid_color::SolidColor;
 pub use checker_texture::CheckerTexture;
 pub use perlin_texture::PerlinTexture;
 pub use image_texture::ImageTexture;
+pub use vertex_color_texture::VertexColorTexture;
+
+pub(crate) use utils::Perlin;
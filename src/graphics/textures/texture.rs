@@ -1,5 +1,10 @@
-use crate::math::{Color, Point};
+use crate::{graphics::HitRecord, math::{Color, Point}};
 
 pub trait Texture: Sync + Send {
     fn value(&self, u: f64, v: f64, p: Point) -> Color;
-}
\ No newline at end of file
+
+    // Looks the texture up at a hit, for textures that need more than its UVs and position
+    fn value_at(&self, hit: &HitRecord) -> Color {
+        self.value(hit.u, hit.v, hit.p)
+    }
+}
@@ -26,6 +26,7 @@ impl Perlin {
         }
     }
 
+    #[allow(clippy::needless_range_loop)]
     fn interpolate(c: Vec<Vec<Vec<Vec3>>>, u: f64, v: f64, w: f64) -> f64 {
         let u = u * u * (3.0 - 2.0 * u);
         let v = v * v * (3.0 - 2.0 * v);
@@ -64,6 +65,7 @@ impl Perlin {
 
     }
 
+    #[allow(clippy::needless_range_loop)]
     pub fn noise(&self, p: Point) -> f64 {
 
         let u = p.x - p.x.floor();
@@ -0,0 +1,34 @@
+use crate::{graphics::HitRecord, math::{Color, Point}};
+
+use super::Texture;
+
+// Colours given per mesh vertex, blended across each triangle. Only meshes report
+// which vertices they hit, so anything else sees the error colour.
+pub struct VertexColorTexture {
+    colors: Vec<Color>,
+}
+
+impl VertexColorTexture {
+    pub fn new(colors: Vec<Color>) -> Self {
+        VertexColorTexture {
+            colors,
+        }
+    }
+}
+
+impl Texture for VertexColorTexture {
+    fn value(&self, _u: f64, _v: f64, _p: Point) -> Color {
+        Color::new(1, 0, 1)
+    }
+
+    fn value_at(&self, hit: &HitRecord) -> Color {
+        match hit.barycentrics {
+            Some(b) => {
+                b.weights[0] * self.colors[b.vertices[0]]
+                    + b.weights[1] * self.colors[b.vertices[1]]
+                    + b.weights[2] * self.colors[b.vertices[2]]
+            }
+            None => self.value(hit.u, hit.v, hit.p),
+        }
+    }
+}
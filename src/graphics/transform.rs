@@ -0,0 +1,279 @@
+use rand::Rng;
+
+use crate::math::{Matrix4, Point, Vec3};
+
+use super::{Aabb, HitRecord, Hittable, Ray};
+
+// Places any hittable in the world with an affine transform. Rays are moved into the
+// object's space to be intersected, and the hits are moved back out again.
+pub struct Transform {
+    object: Box<dyn Hittable>,
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    pub fn new(object: Box<dyn Hittable>, matrix: Matrix4) -> Self {
+        let inverse = match matrix.inverse() {
+            Some(i) => i,
+            None => panic!("Transform matrix cannot be inverted"),
+        };
+
+        Transform {
+            object,
+            matrix,
+            inverse,
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4 {
+        self.matrix
+    }
+
+    pub fn inverse(&self) -> Matrix4 {
+        self.inverse
+    }
+
+    pub fn transform_box(matrix: &Matrix4, b: &Aabb) -> Aabb {
+        let mut minimum = Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut maximum = Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+
+        for i in 0..8 {
+            let corner = Point::new(
+                if i & 1 == 0 { b.minimum.x } else { b.maximum.x },
+                if i & 2 == 0 { b.minimum.y } else { b.maximum.y },
+                if i & 4 == 0 { b.minimum.z } else { b.maximum.z },
+            );
+            let p = matrix.transform_point(corner);
+
+            minimum = Point::new(minimum.x.min(p.x), minimum.y.min(p.y), minimum.z.min(p.z));
+            maximum = Point::new(maximum.x.max(p.x), maximum.y.max(p.y), maximum.z.max(p.z));
+        }
+
+        Aabb::new(minimum, maximum)
+    }
+
+    // Shared with instances, which also hit an object through a pair of matrices
+    pub fn hit_transformed<'a>(
+        object: &'a dyn Hittable,
+        matrix: &Matrix4,
+        inverse: &Matrix4,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<HitRecord<'a>> {
+        let (local_ray, scale) = Transform::local_ray(inverse, ray);
+        let hit = object.hit(&local_ray, t_min * scale, t_max * scale)?;
+
+        Some(Transform::world_hit(hit, matrix, inverse, scale))
+    }
+
+    pub fn hit_surface_transformed<'a>(
+        object: &'a dyn Hittable,
+        matrix: &Matrix4,
+        inverse: &Matrix4,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<HitRecord<'a>> {
+        let (local_ray, scale) = Transform::local_ray(inverse, ray);
+        let hit = object.hit_surface(&local_ray, t_min * scale, t_max * scale)?;
+
+        Some(Transform::world_hit(hit, matrix, inverse, scale))
+    }
+
+    pub fn transmittance_transformed(object: &dyn Hittable, inverse: &Matrix4, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let (local_ray, scale) = Transform::local_ray(inverse, ray);
+        object.transmittance(&local_ray, t_min * scale, t_max * scale)
+    }
+
+    // Rays always have unit directions, so distances are scaled on the way in and out
+    fn local_ray(inverse: &Matrix4, ray: &Ray) -> (Ray, f64) {
+        let local_direction = inverse.transform_vector(ray.direction);
+        let scale = local_direction.length();
+
+        (Ray::new(inverse.transform_point(ray.origin), local_direction, ray.time), scale)
+    }
+
+    fn world_hit<'a>(mut hit: HitRecord<'a>, matrix: &Matrix4, inverse: &Matrix4, scale: f64) -> HitRecord<'a> {
+        hit.t /= scale;
+        hit.p = matrix.transform_point(hit.p);
+        hit.normal = inverse.transform_normal(hit.normal).normalize();
+        hit.tangent = hit.tangent.map(|t| matrix.transform_vector(t).normalize());
+        hit
+    }
+
+    // Directions are mapped into the object's space and back, which squashes or spreads
+    // them out, so the density in solid angle is scaled by how much that changes the
+    // solid angle around the direction
+    pub fn pdf_value_transformed(object: &dyn Hittable, inverse: &Matrix4, origin: Point, direction: Vec3) -> f64 {
+        let local_direction = inverse.transform_vector(direction.normalize());
+        let jacobian = inverse.linear_determinant().abs() / local_direction.length().powi(3);
+
+        object.pdf_value(inverse.transform_point(origin), local_direction) * jacobian
+    }
+
+    pub fn random_transformed(object: &dyn Hittable, matrix: &Matrix4, inverse: &Matrix4, origin: Point) -> Vec3 {
+        matrix.transform_vector(object.random(inverse.transform_point(origin)))
+    }
+
+    // The most the transform scales the area of a surface by, over every orientation. An
+    // area of `normal` is scaled by the determinant times the length of the transformed normal.
+    pub fn max_area_scale(matrix: &Matrix4, inverse: &Matrix4) -> f64 {
+        matrix.linear_determinant().abs() * inverse.max_stretch()
+    }
+
+    // Points picked uniformly over the object are spread unevenly once stretched, so they
+    // are kept in proportion to how much their part of the surface was stretched, relative
+    // to the most any could be. That leaves them uniform over the transformed surface, as
+    // long as its area is given as if every part were stretched the most, with the points
+    // thrown away making up the difference.
+    pub fn sample_surface_transformed<'a>(object: &'a dyn Hittable, matrix: &Matrix4, inverse: &Matrix4) -> Option<HitRecord<'a>> {
+        let mut hit = object.sample_surface()?;
+        if rand::thread_rng().gen::<f64>() > Transform::acceptance(matrix, inverse, hit.normal) {
+            return None;
+        }
+
+        hit.p = matrix.transform_point(hit.p);
+        hit.normal = inverse.transform_normal(hit.normal).normalize();
+        hit.tangent = hit.tangent.map(|t| matrix.transform_vector(t).normalize());
+        Some(hit)
+    }
+
+    // The chance of keeping a point whose local normal is `normal`
+    fn acceptance(matrix: &Matrix4, inverse: &Matrix4, normal: Vec3) -> f64 {
+        let area_scale = matrix.linear_determinant().abs() * inverse.transform_normal(normal).length();
+        area_scale / Transform::max_area_scale(matrix, inverse)
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        Transform::hit_transformed(&*self.object, &self.matrix, &self.inverse, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.object
+            .bounding_box(time0, time1)
+            .map(|b| Transform::transform_box(&self.matrix, &b))
+    }
+
+    fn pdf_value(&self, origin: Point, direction: Vec3) -> f64 {
+        Transform::pdf_value_transformed(&*self.object, &self.inverse, origin, direction)
+    }
+
+    fn random(&self, origin: Point) -> Vec3 {
+        Transform::random_transformed(&*self.object, &self.matrix, &self.inverse, origin)
+    }
+
+    fn area(&self) -> f64 {
+        self.object.area() * Transform::max_area_scale(&self.matrix, &self.inverse)
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord<'_>> {
+        Transform::sample_surface_transformed(&*self.object, &self.matrix, &self.inverse)
+    }
+    fn hit_surface(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        Transform::hit_surface_transformed(&*self.object, &self.matrix, &self.inverse, ray, t_min, t_max)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        Transform::transmittance_transformed(&*self.object, &self.inverse, ray, t_min, t_max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::{
+        graphics::{materials::Lambertian, models::{Sphere, XzRect}},
+        math::Color,
+    };
+
+    fn sphere(center: Point, radius: f64) -> Box<dyn Hittable> {
+        Box::new(Sphere::new(center, radius, Box::new(Lambertian::new_from_color(Color::ONE))))
+    }
+
+    #[test]
+    fn moved_and_scaled_sphere_samples_like_a_sphere_built_there() {
+        let transformed = Transform::new(
+            sphere(Point::ZERO, 1.0),
+            Matrix4::translation(Vec3::new(3.0, 0.0, 0.0)) * Matrix4::scaling(Vec3::ONE * 2.0),
+        );
+        let built = sphere(Point::new(3.0, 0.0, 0.0), 2.0);
+
+        let origin = Point::new(-4.0, 1.0, 0.5);
+        let direction = Point::new(3.0, 0.5, 0.0) - origin;
+        let expected = built.pdf_value(origin, direction);
+        assert!(expected > 0.0);
+        assert!((transformed.pdf_value(origin, direction) - expected).abs() < 1e-9 * expected);
+        assert!((transformed.area() - built.area()).abs() < 1e-9);
+
+        for _ in 0..100 {
+            assert!(built.pdf_value(origin, transformed.random(origin)) > 0.0);
+        }
+    }
+
+    // Midpoints of an n by n grid of equal areas over the sphere of directions
+    fn sphere_grid(n: usize) -> impl Iterator<Item = Vec3> {
+        (0..n * n).map(move |i| {
+            let z = 1.0 - 2.0 * ((i / n) as f64 + 0.5) / n as f64;
+            let phi = 2.0 * PI * ((i % n) as f64 + 0.5) / n as f64;
+            let r = (1.0 - z * z).sqrt();
+            Vec3::new(r * phi.cos(), r * phi.sin(), z)
+        })
+    }
+
+    #[test]
+    fn direction_density_integrates_to_one_when_squashed() {
+        let ellipsoid = Transform::new(
+            sphere(Point::ZERO, 1.0),
+            Matrix4::rotation(Vec3::new(1.0, 1.0, 0.0), 30) * Matrix4::scaling(Vec3::new(3.0, 1.0, 0.5)),
+        );
+        let origin = Point::new(0.5, -1.0, -6.0);
+
+        let n = 1000;
+        let total: f64 = sphere_grid(n).map(|d| ellipsoid.pdf_value(origin, d)).sum::<f64>() * 4.0 * PI
+            / (n * n) as f64;
+        assert!((total - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn surface_samples_cover_the_stretched_area() {
+        let matrix = Matrix4::scaling(Vec3::new(2.0, 1.0, 3.0)) * Matrix4::rotation_x(30);
+        let inverse = matrix.inverse().unwrap();
+        let rect = Transform::new(Box::new(XzRect::new(0.0, 1.0, 0.0, 1.0, 0.0, Box::new(Lambertian::new_from_color(Color::ONE)))), matrix);
+
+        let edges = (matrix.transform_vector(Vec3::RIGHT), matrix.transform_vector(Vec3::FORWARD));
+        let true_area = edges.0.cross(edges.1).length();
+
+        // The rect is flat, so every point is kept with the same chance
+        let kept = Transform::acceptance(&matrix, &inverse, Vec3::UP);
+        assert!((kept * rect.area() - true_area).abs() < 1e-9 * true_area);
+
+        for _ in 0..100 {
+            if let Some(hit) = rect.sample_surface() {
+                assert!(hit.normal.dot(edges.0).abs() < 1e-9 && hit.normal.dot(edges.1).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn kept_sphere_samples_cover_the_spheroid_area() {
+        let matrix = Matrix4::scaling(Vec3::new(2.0, 2.0, 1.0));
+        let inverse = matrix.inverse().unwrap();
+        let spheroid = Transform::new(sphere(Point::ZERO, 1.0), matrix);
+
+        // The surface area of an oblate spheroid with radii a, a and c
+        let (a, c) = (2.0f64, 1.0f64);
+        let e = (1.0 - c * c / (a * a)).sqrt();
+        let true_area = 2.0 * PI * a * a * (1.0 + (1.0 - e * e) / e * e.atanh());
+
+        let n = 400;
+        let kept = sphere_grid(n).map(|normal| Transform::acceptance(&matrix, &inverse, normal)).sum::<f64>()
+            / (n * n) as f64;
+        assert!((kept * spheroid.area() - true_area).abs() < 1e-4 * true_area);
+    }
+}
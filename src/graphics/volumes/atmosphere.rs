@@ -0,0 +1,109 @@
+use rand::Rng;
+
+use crate::{
+    graphics::{HitRecord, Ray, materials::HenyeyGreenstein},
+    math::{Color, Vec3},
+};
+
+// What happened to a ray on its way through the atmosphere. Either it scattered
+// somewhere along the way, where the hit uses the atmosphere's phase function as its
+// material, or it reached the end of its segment. Both carry the weight to multiply the
+// light found afterwards by.
+pub enum AtmosphereEvent<'a> {
+    Scattered(HitRecord<'a>, Color),
+    Transmitted(Color),
+}
+
+// A homogeneous medium filling the whole scene, for depth haze and light shafts. The
+// coefficients are per unit distance and per colour channel. Without a radius it fills
+// all of space, which hides the background entirely from anything it doesn't scatter.
+pub struct Atmosphere {
+    absorption: Color,
+    scattering: Color,
+    radius: f64,
+    phase_function: HenyeyGreenstein,
+}
+
+impl Atmosphere {
+    pub fn new(absorption: Color, scattering: Color) -> Self {
+        Atmosphere {
+            absorption,
+            scattering,
+            radius: f64::INFINITY,
+            phase_function: HenyeyGreenstein::new_from_color(Color::ONE, 0.0),
+        }
+    }
+
+    // Limits the atmosphere to a sphere around the origin, beyond which the background shows
+    pub fn set_radius(self, radius: f64) -> Self {
+        Atmosphere { radius, ..self }
+    }
+
+    pub fn set_anisotropy(self, g: f64) -> Self {
+        Atmosphere {
+            phase_function: self.phase_function.set_anisotropy(g),
+            ..self
+        }
+    }
+
+    fn extinction(&self) -> Color {
+        self.absorption + self.scattering
+    }
+
+    // The part of a ray between t_min and t_max that lies inside the atmosphere
+    fn extent(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        if self.radius.is_infinite() {
+            return Some((t_min, t_max));
+        }
+
+        let half_b = ray.origin.dot(ray.direction);
+        let c = ray.origin.length_squared() - self.radius * self.radius;
+        let discriminant = half_b * half_b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let sqrtd = discriminant.sqrt();
+        let start = (-half_b - sqrtd).max(t_min);
+        let end = (-half_b + sqrtd).min(t_max);
+        if start < end { Some((start, end)) } else { None }
+    }
+
+    // Samples a distance along the ray in proportion to the average extinction of the
+    // colour channels, then weights the result to account for each channel's own
+    pub fn sample(&self, ray: &Ray, t_min: f64, t_max: f64) -> AtmosphereEvent<'_> {
+        let extinction = self.extinction();
+        let average = (extinction.x + extinction.y + extinction.z) / 3.0;
+
+        let (start, end) = match self.extent(ray, t_min, t_max) {
+            Some(e) if average > 0.0 => e,
+            _ => return AtmosphereEvent::Transmitted(Color::ONE),
+        };
+
+        let transmittance = |distance: f64| {
+            Color::new(
+                (-extinction.x * distance).exp(),
+                (-extinction.y * distance).exp(),
+                (-extinction.z * distance).exp(),
+            )
+        };
+
+        let distance = -(1.0 - rand::thread_rng().gen::<f64>()).ln() / average;
+        if start + distance < end {
+            let t = start + distance;
+            let p = ray.at(t);
+            let pdf = average * (-average * distance).exp();
+            let weight = self.scattering * transmittance(distance) / pdf;
+
+            let hit = HitRecord::new(p, Vec3::RIGHT, t, 0.0, 0.0, ray, &self.phase_function);
+            return AtmosphereEvent::Scattered(hit, weight);
+        }
+
+        if end.is_infinite() {
+            return AtmosphereEvent::Transmitted(Color::ZERO);
+        }
+
+        let probability = (-average * (end - start)).exp();
+        AtmosphereEvent::Transmitted(transmittance(end - start) / probability)
+    }
+}
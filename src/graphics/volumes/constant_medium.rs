@@ -0,0 +1,60 @@
+use rand::Rng;
+
+use crate::{
+    graphics::{Aabb, HitRecord, Hittable, Ray, materials::{Isotropic, Material}, textures::Texture},
+    math::{Color, Vec3},
+};
+
+use super::segments::BoundarySegments;
+
+// A volume of uniform density filling a closed boundary, such as a sphere or a box.
+// Rays passing through it may scatter at any point, with the distance travelled before
+// scattering following an exponential distribution.
+pub struct ConstantMedium {
+    boundary: Box<dyn Hittable>,
+    neg_inv_density: f64,
+    phase_function: Box<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Box<dyn Hittable>, density: f64, texture: Box<dyn Texture>) -> Self {
+        ConstantMedium {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function: Box::new(Isotropic::new(texture)),
+        }
+    }
+
+    pub fn new_from_color(boundary: Box<dyn Hittable>, density: f64, color: Color) -> Self {
+        ConstantMedium {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function: Box::new(Isotropic::new_from_color(color)),
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut rng = rand::thread_rng();
+
+        // The free flight distance is memoryless, so one sample can be spent across every
+        // stretch of the ray inside the boundary
+        let mut remaining = self.neg_inv_density * rng.gen::<f64>().ln();
+
+        // Ray directions are unit length, so segment lengths are also distances travelled
+        for (start, end) in BoundarySegments::new(&*self.boundary, ray, t_min, t_max) {
+            if remaining < end - start {
+                let t = start + remaining;
+                return Some(HitRecord::new(ray.at(t), Vec3::RIGHT, t, 0.0, 0.0, ray, &*self.phase_function));
+            }
+            remaining -= end - start;
+        }
+
+        None
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.boundary.bounding_box(time0, time1)
+    }
+}
@@ -0,0 +1,9 @@
+use crate::math::Point;
+
+// A density that varies through space, for volumes such as clouds and smoke
+pub trait DensityField: Sync + Send {
+    fn density(&self, p: Point) -> f64;
+
+    // An upper bound on the density anywhere in the field, used as the tracking majorant
+    fn max_density(&self) -> f64;
+}
@@ -0,0 +1,178 @@
+use rand::Rng;
+
+use crate::{
+    graphics::{Aabb, HitRecord, Hittable, Ray, materials::HenyeyGreenstein, textures::Texture},
+    math::Vec3,
+};
+
+use super::{DensityField, segments::BoundarySegments};
+
+// A volume filling a closed boundary whose density is scaled from a field, such as noise
+// or a voxel grid. Collisions are found with delta tracking: tentative collisions are
+// sampled against the field's maximum density and each is accepted as real in proportion
+// to the density where it lands. Shadow rays see through the medium instead, and are
+// dimmed by its transmittance.
+pub struct HeterogeneousMedium {
+    boundary: Box<dyn Hittable>,
+    field: Box<dyn DensityField>,
+    density: f64,
+    phase_function: HenyeyGreenstein,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(boundary: Box<dyn Hittable>, field: Box<dyn DensityField>, density: f64, albedo: Box<dyn Texture>) -> Self {
+        HeterogeneousMedium {
+            boundary,
+            field,
+            density,
+            phase_function: HenyeyGreenstein::new(albedo, 0.0),
+        }
+    }
+
+    pub fn set_emission(self, emission: Box<dyn Texture>) -> Self {
+        HeterogeneousMedium {
+            phase_function: self.phase_function.set_emission(emission),
+            ..self
+        }
+    }
+
+    // Positive values scatter light forwards, like clouds, and negative values backwards
+    pub fn set_anisotropy(self, g: f64) -> Self {
+        HeterogeneousMedium {
+            phase_function: self.phase_function.set_anisotropy(g),
+            ..self
+        }
+    }
+
+    fn majorant(&self) -> f64 {
+        self.density * self.field.max_density()
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let majorant = self.majorant();
+        if majorant <= 0.0 {
+            return None;
+        }
+
+        let mut rng = rand::thread_rng();
+
+        for (start, end) in BoundarySegments::new(&*self.boundary, ray, t_min, t_max) {
+            let mut t = start;
+            loop {
+                t -= (1.0 - rng.gen::<f64>()).ln() / majorant;
+                if t >= end {
+                    break;
+                }
+
+                let p = ray.at(t);
+                if rng.gen::<f64>() * majorant < self.density * self.field.density(p) {
+                    return Some(HitRecord::new(p, Vec3::RIGHT, t, 0.0, 0.0, ray, &self.phase_function));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.boundary.bounding_box(time0, time1)
+    }
+
+    fn hit_surface(&self, _ray: &Ray, _t_min: f64, _t_max: f64) -> Option<HitRecord<'_>> {
+        None
+    }
+
+    // Estimated with ratio tracking, which weights each tentative collision by the chance
+    // of it being null rather than stopping at the first real one
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let majorant = self.majorant();
+        if majorant <= 0.0 {
+            return 1.0;
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut transmittance = 1.0;
+
+        for (start, end) in BoundarySegments::new(&*self.boundary, ray, t_min, t_max) {
+            let mut t = start;
+            loop {
+                t -= (1.0 - rng.gen::<f64>()).ln() / majorant;
+                if t >= end {
+                    break;
+                }
+                transmittance *= 1.0 - self.density * self.field.density(ray.at(t)) / majorant;
+            }
+        }
+
+        transmittance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graphics::{
+            Transform,
+            materials::Lambertian,
+            models::{Sphere, XyRect},
+            textures::SolidColor,
+        },
+        math::{Color, Matrix4, Point},
+    };
+
+    struct UniformField(f64);
+
+    impl DensityField for UniformField {
+        fn density(&self, _p: Point) -> f64 {
+            self.0
+        }
+
+        fn max_density(&self) -> f64 {
+            1.0
+        }
+    }
+
+    fn medium(field: f64, density: f64) -> HeterogeneousMedium {
+        HeterogeneousMedium::new(
+            Box::new(Sphere::new(Point::ZERO, 1.0, Box::new(Lambertian::new_from_color(Color::ONE)))),
+            Box::new(UniformField(field)),
+            density,
+            Box::new(SolidColor::new(Color::new(0.3, 0.5, 1.0))),
+        )
+    }
+
+    #[test]
+    fn shadow_rays_see_through_to_the_surface_behind() {
+        let wall: Box<dyn Hittable> = Box::new(XyRect::new(-5.0, 5.0, -5.0, 5.0, 3.0, Box::new(Lambertian::new_from_color(Color::ONE))));
+        let fog: Box<dyn Hittable> = Box::new(Transform::new(Box::new(medium(1.0, 1e6)), Matrix4::translation(Vec3::new(0.0, 0.0, 1.0))));
+        let world: Vec<Box<dyn Hittable>> = vec![wall, fog];
+        let ray = Ray::new(Point::new(0.0, 0.0, -2.0), Vec3::FORWARD, 0.0);
+
+        assert!(world.hit(&ray, 0.001, f64::INFINITY).unwrap().t < 2.01);
+        assert!((world.hit_surface(&ray, 0.001, f64::INFINITY).unwrap().t - 5.0).abs() < 1e-9);
+        assert_eq!(world.transmittance(&ray, 0.001, 5.0), 0.0);
+        assert_eq!(world.transmittance(&ray, 0.001, 0.5), 1.0);
+    }
+
+    #[test]
+    fn null_collisions_let_all_light_through() {
+        let empty = medium(0.0, 50.0);
+        let ray = Ray::new(Point::new(0.0, 0.0, -2.0), Vec3::FORWARD, 0.0);
+
+        assert!(empty.hit(&ray, 0.001, f64::INFINITY).is_none());
+        assert_eq!(empty.transmittance(&ray, 0.001, f64::INFINITY), 1.0);
+    }
+
+    #[test]
+    fn emission_is_weighted_by_absorption() {
+        let glowing = medium(1.0, 1e6).set_emission(Box::new(SolidColor::new(Color::ONE * 2.0)));
+        let ray = Ray::new(Point::new(0.0, 0.0, -2.0), Vec3::FORWARD, 0.0);
+        let hit = glowing.hit(&ray, 0.001, f64::INFINITY).unwrap();
+
+        let emitted = hit.material.emitted(hit.u, hit.v, hit.p);
+        assert!((emitted - Color::new(1.4, 1.0, 0.0)).near_zero());
+    }
+}
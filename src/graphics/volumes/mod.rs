@@ -0,0 +1,16 @@
+mod density_field;
+
+mod atmosphere;
+mod constant_medium;
+mod heterogeneous_medium;
+mod noise_density;
+mod segments;
+mod voxel_grid;
+
+pub use density_field::DensityField;
+
+pub use atmosphere::{Atmosphere, AtmosphereEvent};
+pub use constant_medium::ConstantMedium;
+pub use heterogeneous_medium::HeterogeneousMedium;
+pub use noise_density::NoiseDensity;
+pub use voxel_grid::VoxelGrid;
@@ -0,0 +1,30 @@
+use crate::{graphics::textures::Perlin, math::Point};
+
+use super::DensityField;
+
+const TURBULENCE_DEPTH: usize = 7;
+
+// Turbulent Perlin noise, clamped to lie between zero and one
+pub struct NoiseDensity {
+    noise: Perlin,
+    scale: f64,
+}
+
+impl NoiseDensity {
+    pub fn new(scale: f64) -> Self {
+        NoiseDensity {
+            noise: Perlin::new(),
+            scale,
+        }
+    }
+}
+
+impl DensityField for NoiseDensity {
+    fn density(&self, p: Point) -> f64 {
+        self.noise.turb(self.scale * p, TURBULENCE_DEPTH).min(1.0)
+    }
+
+    fn max_density(&self) -> f64 {
+        1.0
+    }
+}
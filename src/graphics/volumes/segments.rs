@@ -0,0 +1,44 @@
+use crate::graphics::{Hittable, Ray};
+
+// Walks the stretches of a ray that lie inside a closed boundary, clamped to [t_min, t_max].
+// Searching starts from behind the ray origin, so a ray that begins inside the boundary
+// still finds where it entered. Boundaries don't need to be convex.
+pub(super) struct BoundarySegments<'a> {
+    boundary: &'a dyn Hittable,
+    ray: &'a Ray,
+    t_min: f64,
+    t_max: f64,
+    search_from: f64,
+}
+
+impl<'a> BoundarySegments<'a> {
+    pub fn new(boundary: &'a dyn Hittable, ray: &'a Ray, t_min: f64, t_max: f64) -> Self {
+        BoundarySegments {
+            boundary,
+            ray,
+            t_min,
+            t_max,
+            search_from: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl Iterator for BoundarySegments<'_> {
+    type Item = (f64, f64);
+
+    fn next(&mut self) -> Option<(f64, f64)> {
+        while self.search_from < self.t_max {
+            let entry = self.boundary.hit(self.ray, self.search_from, f64::INFINITY)?;
+            let exit = self.boundary.hit(self.ray, entry.t + 0.0001, f64::INFINITY)?;
+            self.search_from = exit.t + 0.0001;
+
+            let start = entry.t.max(self.t_min);
+            let end = exit.t.min(self.t_max);
+            if start < end {
+                return Some((start, end));
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{graphics::Aabb, math::Point};
+
+use super::DensityField;
+
+// A dense grid of density samples stretched over a box, with x varying fastest.
+// Samples sit at the centres of their cells and are blended trilinearly.
+pub struct VoxelGrid {
+    resolution: (usize, usize, usize),
+    values: Vec<f64>,
+    bounds: Aabb,
+    max_value: f64,
+}
+
+impl VoxelGrid {
+    pub fn new(resolution: (usize, usize, usize), values: Vec<f64>, bounds: Aabb) -> Self {
+        let (x, y, z) = resolution;
+        if x * y * z != values.len() || values.is_empty() {
+            panic!("VoxelGrid expected {} values but got {}", x * y * z, values.len());
+        }
+
+        let max_value = values.iter().cloned().fold(0.0, f64::max);
+
+        VoxelGrid {
+            resolution,
+            values,
+            bounds,
+            max_value,
+        }
+    }
+
+    pub fn resolution(&self) -> (usize, usize, usize) {
+        self.resolution
+    }
+
+    // The box the grid is stretched over, which makes a natural boundary for its volume
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
+    fn value(&self, x: usize, y: usize, z: usize) -> f64 {
+        let (width, height, _) = self.resolution;
+        self.values[(z * height + y) * width + x]
+    }
+}
+
+// Finds the two samples either side of a position along one axis and the blend between them
+fn lerp_cells(offset: f64, extent: f64, count: usize) -> (usize, usize, f64) {
+    let position = (offset / extent * count as f64 - 0.5).clamp(0.0, (count - 1) as f64);
+    let low = position.floor() as usize;
+    let high = (low + 1).min(count - 1);
+    (low, high, position - low as f64)
+}
+
+impl DensityField for VoxelGrid {
+    fn density(&self, p: Point) -> f64 {
+        let min = self.bounds.minimum;
+        let max = self.bounds.maximum;
+        if p.x < min.x || p.y < min.y || p.z < min.z || p.x > max.x || p.y > max.y || p.z > max.z {
+            return 0.0;
+        }
+
+        let extent = max - min;
+        let (x0, x1, fx) = lerp_cells(p.x - min.x, extent.x, self.resolution.0);
+        let (y0, y1, fy) = lerp_cells(p.y - min.y, extent.y, self.resolution.1);
+        let (z0, z1, fz) = lerp_cells(p.z - min.z, extent.z, self.resolution.2);
+
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let front = lerp(
+            lerp(self.value(x0, y0, z0), self.value(x1, y0, z0), fx),
+            lerp(self.value(x0, y1, z0), self.value(x1, y1, z0), fx),
+            fy,
+        );
+        let back = lerp(
+            lerp(self.value(x0, y0, z1), self.value(x1, y0, z1), fx),
+            lerp(self.value(x0, y1, z1), self.value(x1, y1, z1), fx),
+            fy,
+        );
+
+        lerp(front, back, fz)
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max_value
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{
+    graphics::{Hittable, Ray},
+    math::{Color, Vec3},
+    utils::Config,
+};
+
+use super::Integrator;
+
+// Shades the first surface a ray hits by how much of the hemisphere above it is open,
+// looking no further than `radius`. Misses are left black.
+pub struct AmbientOcclusion {
+    radius: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(radius: f64) -> Self {
+        AmbientOcclusion { radius }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, ray: Ray, config: &Config) -> Color {
+        let hit = match config.world.hit(&ray, 0.001, f64::INFINITY) {
+            Some(h) => h,
+            None => return Color::ZERO,
+        };
+
+        // A cosine weighted direction, like a Lambertian bounce
+        let mut direction = hit.normal + Vec3::random_unit_vector();
+        if direction.near_zero() {
+            direction = hit.normal;
+        }
+
+        let occlusion_ray = Ray::new(hit.p, direction, ray.time);
+        if config.world.hit(&occlusion_ray, 0.001, self.radius).is_some() {
+            Color::ZERO
+        } else {
+            Color::ONE
+        }
+    }
+}
@@ -0,0 +1,542 @@
+use rand::Rng;
+
+use crate::{
+    graphics::{HitRecord, Hittable, Ray, volumes::AtmosphereEvent},
+    math::{Color, Point, Vec3},
+    output::SplatBuffer,
+    utils::Config,
+};
+
+use super::{
+    Integrator,
+    lighting::{emission_pdf, on_light, power_heuristic, sample_background, sample_emission, shadow_transmittance},
+};
+
+// Traces one subpath from the camera and one from a light, then connects every prefix of
+// one to every prefix of the other. Each way of making a path is weighted against the
+// others by the power heuristic, so light that is hard to find from the camera, such as
+// through small openings, is found from the lights instead. Connections straight to the
+// camera are splatted into whichever pixel they land on.
+//
+// Only the scene's lights start light subpaths. The background and emitters which aren't
+// among the lights are found from the camera alone, like the path tracer does.
+pub struct BidirectionalPathTracer;
+
+impl BidirectionalPathTracer {
+    pub fn new() -> Self {
+        BidirectionalPathTracer
+    }
+
+    // Without anywhere to splat to, connections to the camera are left out and the other
+    // strategies are weighted as if they didn't exist
+    fn trace(&self, ray: Ray, config: &Config, splats: Option<&SplatBuffer>) -> Color {
+        let time = ray.time;
+        let max_depth = config.max_depth as usize;
+
+        let mut camera_path = Vec::new();
+        let mut radiance = camera_subpath(config, ray, &mut camera_path);
+
+        let mut light_path = Vec::new();
+        light_subpath(config, time, &mut light_path);
+
+        for t in 1..=camera_path.len() {
+            for s in 0..=light_path.len() {
+                // Paths need at least two vertices, and a light connected straight to the
+                // camera is already seen by camera rays
+                if s + t < 2 || (s == 1 && t == 1) || s + t - 1 > max_depth {
+                    continue;
+                }
+
+                if t == 1 {
+                    if let Some(splats) = splats {
+                        splat_to_camera(config, &camera_path, &light_path, s, splats);
+                    }
+                } else {
+                    radiance = radiance + connect(config, &camera_path, &light_path, s, t, splats.is_some());
+                }
+            }
+        }
+
+        radiance
+    }
+}
+
+impl Integrator for BidirectionalPathTracer {
+    fn radiance(&self, ray: Ray, config: &Config) -> Color {
+        self.trace(ray, config, None)
+    }
+
+    fn sample(&self, ray: Ray, config: &Config, splats: &SplatBuffer) -> Color {
+        self.trace(ray, config, Some(splats))
+    }
+}
+
+impl Default for BidirectionalPathTracer {
+    fn default() -> Self {
+        BidirectionalPathTracer::new()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum VertexKind {
+    Camera,
+    Light,
+    Surface,
+    Medium,
+}
+
+// One point along a subpath from the camera or from a light
+struct Vertex<'a> {
+    kind: VertexKind,
+    p: Point,
+    normal: Vec3,
+    time: f64,
+    // What was hit here and the ray that hit it, for evaluating its material
+    hit: Option<HitRecord<'a>>,
+    incoming: Option<Ray>,
+    // The contribution of the subpath up to this vertex over the density of sampling it
+    beta: Color,
+    // Whether the path carried on from here with a specular bounce, which can't be connected
+    delta: bool,
+    // The density in area of sampling this vertex from the one before it, and from the one
+    // after it had the subpath been traced the other way
+    pdf_fwd: f64,
+    pdf_rev: f64,
+}
+
+impl<'a> Vertex<'a> {
+    fn new(kind: VertexKind, p: Point, normal: Vec3, time: f64, beta: Color) -> Self {
+        Vertex {
+            kind,
+            p,
+            normal,
+            time,
+            hit: None,
+            incoming: None,
+            beta,
+            delta: false,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0,
+        }
+    }
+
+    // Media scatter light arriving from any angle equally, but surfaces are foreshortened
+    fn on_surface(&self) -> bool {
+        matches!(self.kind, VertexKind::Light | VertexKind::Surface)
+    }
+
+    // Whether a light subpath could have started here
+    fn on_light(&self, config: &Config) -> bool {
+        match (&self.hit, &self.incoming) {
+            (Some(hit), Some(ray)) if self.kind == VertexKind::Surface => on_light(config, ray, hit.t),
+            _ => false,
+        }
+    }
+
+    // Turns a density in solid angle at `from` into a density in area at this vertex
+    fn convert_density(&self, pdf: f64, from: &Vertex) -> f64 {
+        let offset = self.p - from.p;
+        let distance_squared = offset.length_squared();
+        if distance_squared == 0.0 {
+            return 0.0;
+        }
+
+        let pdf = pdf / distance_squared;
+        if self.on_surface() {
+            pdf * self.normal.dot(offset).abs() / distance_squared.sqrt()
+        } else {
+            pdf
+        }
+    }
+
+    // The light scattered from here towards `next` for each unit arriving along the path
+    fn f(&self, next: &Vertex) -> Color {
+        match (&self.hit, &self.incoming) {
+            (Some(hit), Some(ray)) => hit.material.eval(ray, hit, &Ray::new(self.p, next.p - self.p, self.time)),
+            _ => Color::ZERO,
+        }
+    }
+
+    // The density in area at `next` of carrying on to it from here, having come from `prev`
+    fn pdf(&self, config: &Config, prev: Option<&Vertex>, next: &Vertex) -> f64 {
+        let direction = next.p - self.p;
+        let pdf = match self.kind {
+            VertexKind::Camera => camera_pdf(config, direction),
+            VertexKind::Light => return self.pdf_light(next),
+            VertexKind::Surface | VertexKind::Medium => match (prev, &self.hit) {
+                (Some(prev), Some(hit)) => {
+                    let ray = Ray::new(prev.p, self.p - prev.p, self.time);
+                    hit.material.scattering_pdf(&ray, hit, &Ray::new(self.p, direction, self.time))
+                }
+                _ => return 0.0,
+            },
+        };
+
+        next.convert_density(pdf, self)
+    }
+
+    // The density in area at `next` of this vertex, as a light, emitting towards it
+    fn pdf_light(&self, next: &Vertex) -> f64 {
+        next.convert_density(emission_pdf(self.normal, (next.p - self.p).normalize()), self)
+    }
+}
+
+// The density in area of a light subpath starting at any given point on the lights
+fn pdf_light_origin(config: &Config) -> f64 {
+    1.0 / config.lights.area()
+}
+
+// `render_image` gives pixel i the screen coordinates [i, i + 1] / (width - 1), so the
+// pixels cover a little more than the camera's viewport. Camera densities and importance
+// are spread over that whole area instead.
+fn film_scale(config: &Config) -> f64 {
+    let (width, height) = config.image_size;
+    ((width - 1) * (height - 1)) as f64 / (width * height) as f64
+}
+
+fn camera_pdf(config: &Config, direction: Vec3) -> f64 {
+    config.camera.pdf_direction(direction) * film_scale(config)
+}
+
+// Starts `path` with the camera and follows `ray` from it, returning the background
+// light found on the way
+fn camera_subpath<'a>(config: &'a Config, ray: Ray, path: &mut Vec<Vertex<'a>>) -> Color {
+    let pdf_dir = camera_pdf(config, ray.direction);
+    path.push(Vertex::new(VertexKind::Camera, ray.origin, Vec3::ZERO, ray.time, Color::ONE));
+
+    random_walk(config, ray, Color::ONE, pdf_dir, config.max_depth as usize, true, path)
+}
+
+// Starts `path` at a point picked uniformly over the lights and follows a cosine
+// weighted direction from it
+fn light_subpath<'a>(config: &'a Config, time: f64, path: &mut Vec<Vertex<'a>>) {
+    let (hit, ray) = match sample_emission(config, time) {
+        Some(e) => e,
+        None => return,
+    };
+
+    let emitted = hit.material.emitted(hit.u, hit.v, hit.p);
+    let pdf_pos = pdf_light_origin(config);
+    let pdf_dir = emission_pdf(hit.normal, ray.direction);
+
+    let mut light = Vertex::new(VertexKind::Light, hit.p, hit.normal, time, emitted / pdf_pos);
+    light.pdf_fwd = pdf_pos;
+    let beta = emitted * (hit.normal.dot(ray.direction).abs() / (pdf_pos * pdf_dir));
+    light.hit = Some(hit);
+    path.push(light);
+
+    if pdf_dir > 0.0 {
+        random_walk(config, ray, beta, pdf_dir, config.max_depth.saturating_sub(1) as usize, false, path);
+    }
+}
+
+// Extends `path` by following `ray`, which left the last vertex with density `pdf_dir`,
+// until it escapes, is absorbed or `max_vertices` have been added. Subpaths from the
+// camera also add up the background they see, weighted against sampling it directly.
+fn random_walk<'a>(
+    config: &'a Config,
+    ray: Ray,
+    beta: Color,
+    pdf_dir: f64,
+    max_vertices: usize,
+    from_camera: bool,
+    path: &mut Vec<Vertex<'a>>,
+) -> Color {
+    let mut rng = rand::thread_rng();
+
+    let mut ray = ray;
+    let mut beta = beta;
+    let mut pdf_fwd = pdf_dir;
+    let mut background = Color::ZERO;
+    // Russian roulette only looks at what the path has lost along the way, as the
+    // emission starting a light subpath can be any size
+    let mut throughput = Color::ONE;
+    let mut scattering_pdf: Option<f64> = None;
+
+    for bounce in 0..max_vertices {
+        let mut hit = config.world.hit(&ray, 0.001, f64::INFINITY);
+
+        if let Some(atmosphere) = &config.atmosphere {
+            let t_max = hit.as_ref().map_or(f64::INFINITY, |h| h.t);
+            let w = match atmosphere.sample(&ray, 0.001, t_max) {
+                AtmosphereEvent::Scattered(medium_hit, w) => {
+                    hit = Some(medium_hit);
+                    w
+                }
+                AtmosphereEvent::Transmitted(w) => w,
+            };
+            beta = beta * w;
+            throughput = throughput * w;
+        }
+
+        let hit = match hit {
+            Some(h) => h,
+            None => {
+                if from_camera {
+                    let mut value = config.background.value(ray.direction);
+                    if let Some(pdf) = scattering_pdf {
+                        value = value * power_heuristic(pdf, config.background.pdf(ray.direction));
+                    }
+                    background = background + beta * value;
+                }
+                break;
+            }
+        };
+
+        let kind = if hit.material.is_volumetric() { VertexKind::Medium } else { VertexKind::Surface };
+        let mut vertex = Vertex::new(kind, hit.p, hit.normal, ray.time, beta);
+        vertex.pdf_fwd = vertex.convert_density(pdf_fwd, &path[path.len() - 1]);
+
+        let scattered = if bounce + 1 < max_vertices {
+            hit.material.scatter(&ray, &hit)
+        } else {
+            None
+        };
+
+        let mut pdf_rev = 0.0;
+        if let Some((scattered_ray, _)) = &scattered {
+            let pdf = hit.material.scattering_pdf(&ray, &hit, scattered_ray);
+            if pdf > 0.0 {
+                // Scattering back the way the path came, had it arrived along the new direction
+                let reversed = Ray::new(hit.p + scattered_ray.direction, -scattered_ray.direction, ray.time);
+                pdf_rev = hit.material.scattering_pdf(&reversed, &hit, &Ray::new(hit.p, -ray.direction, ray.time));
+
+                if from_camera {
+                    background = background + beta * sample_background(&ray, &hit, config, true);
+                }
+                scattering_pdf = Some(pdf);
+            } else {
+                vertex.delta = true;
+                scattering_pdf = None;
+            }
+            pdf_fwd = pdf;
+        }
+
+        vertex.hit = Some(hit);
+        vertex.incoming = Some(ray);
+        path.push(vertex);
+
+        let n = path.len();
+        path[n - 2].pdf_rev = path[n - 2].convert_density(pdf_rev, &path[n - 1]);
+
+        let (scattered_ray, attenuation) = match scattered {
+            Some(s) => s,
+            None => break,
+        };
+        beta = beta * attenuation;
+        throughput = throughput * attenuation;
+        ray = scattered_ray;
+
+        if bounce + 1 >= config.russian_roulette_depth as usize {
+            let survival = throughput.x.max(throughput.y).max(throughput.z).min(1.0);
+            if rng.gen::<f64>() >= survival {
+                break;
+            }
+            beta = beta / survival;
+            throughput = throughput / survival;
+        }
+    }
+
+    background
+}
+
+// The light carried by the first `s` light vertices joined to the first `t` camera
+// vertices, for t of at least two, weighted against the other strategies
+fn connect(config: &Config, camera_path: &[Vertex], light_path: &[Vertex], s: usize, t: usize, light_tracing: bool) -> Color {
+    let pt = &camera_path[t - 1];
+
+    match s {
+        // The camera subpath found an emitter by itself
+        0 => {
+            let hit = match &pt.hit {
+                Some(h) => h,
+                None => return Color::ZERO,
+            };
+            let emitted = hit.material.emitted(hit.u, hit.v, hit.p);
+            if emitted.near_zero() {
+                return Color::ZERO;
+            }
+
+            // Nothing else can find emitters which aren't among the lights
+            let weight = if pt.on_light(config) {
+                mis_weight(config, camera_path, light_path, None, s, t, light_tracing)
+            } else {
+                1.0
+            };
+            pt.beta * emitted * weight
+        }
+        // A new point on the lights is sampled from the camera subpath, as it is closer
+        // than the one starting the light subpath
+        1 => {
+            let (hit, incoming) = match (&pt.hit, &pt.incoming) {
+                (Some(h), Some(r)) => (h, r),
+                _ => return Color::ZERO,
+            };
+            if config.lights.is_empty() {
+                return Color::ZERO;
+            }
+
+            let light_ray = Ray::new(pt.p, config.lights.random(pt.p), pt.time);
+            let pdf = config.lights.pdf_value(pt.p, light_ray.direction);
+            let f = hit.material.eval(incoming, hit, &light_ray);
+            if pdf <= 0.0 || f.near_zero() {
+                return Color::ZERO;
+            }
+
+            let light_hit = match config.world.hit_surface(&light_ray, 0.001, f64::INFINITY) {
+                Some(h) if on_light(config, &light_ray, h.t) => h,
+                _ => return Color::ZERO,
+            };
+            let emitted = light_hit.material.emitted(light_hit.u, light_hit.v, light_hit.p);
+            if emitted.near_zero() {
+                return Color::ZERO;
+            }
+
+            let transmittance = shadow_transmittance(&light_ray, light_hit.t, config);
+            let mut light = Vertex::new(VertexKind::Light, light_hit.p, light_hit.normal, pt.time, emitted / pdf);
+            light.pdf_fwd = pdf_light_origin(config);
+
+            let weight = mis_weight(config, camera_path, light_path, Some(&light), s, t, light_tracing);
+            pt.beta * f * emitted * transmittance * weight / pdf
+        }
+        _ => {
+            let qs = &light_path[s - 1];
+            let f = qs.f(pt) * pt.f(qs);
+            if f.near_zero() {
+                return Color::ZERO;
+            }
+
+            let offset = qs.p - pt.p;
+            let distance = offset.length();
+            let ray = Ray::new(pt.p, offset, pt.time);
+            if config.world.hit_surface(&ray, 0.001, distance - 0.001).is_some() {
+                return Color::ZERO;
+            }
+
+            let transmittance = shadow_transmittance(&ray, distance, config);
+            let weight = mis_weight(config, camera_path, light_path, None, s, t, light_tracing);
+            qs.beta * f * pt.beta * transmittance * weight / (distance * distance)
+        }
+    }
+}
+
+// Joins the first `s` light vertices to a new point on the lens, and adds the light they
+// carry to the pixel that point sees them through
+fn splat_to_camera(config: &Config, camera_path: &[Vertex], light_path: &[Vertex], s: usize, splats: &SplatBuffer) {
+    let qs = &light_path[s - 1];
+    let lens_point = config.camera.sample_lens();
+
+    let (u, v) = match config.camera.project(lens_point, qs.p) {
+        Some(c) => c,
+        None => return,
+    };
+    let (width, height) = config.image_size;
+    let x = (u * (width - 1) as f64).floor();
+    let y = (v * (height - 1) as f64).floor();
+    if x < 0.0 || y < 0.0 || x >= width as f64 || y >= height as f64 {
+        return;
+    }
+
+    let mut camera = Vertex::new(VertexKind::Camera, lens_point, Vec3::ZERO, qs.time, Color::ZERO);
+    let f = qs.f(&camera);
+    if f.near_zero() {
+        return;
+    }
+
+    let offset = lens_point - qs.p;
+    let ray = Ray::new(qs.p, offset, qs.time);
+    if config.world.hit_surface(&ray, 0.001, offset.length()).is_some() {
+        return;
+    }
+
+    let importance = config.camera.importance(-offset) * film_scale(config);
+    let pdf = config.camera.pdf_lens(lens_point, qs.p);
+    if pdf <= 0.0 {
+        return;
+    }
+    camera.beta = Color::ONE * (importance / pdf);
+
+    let transmittance = shadow_transmittance(&ray, offset.length(), config);
+    let weight = mis_weight(config, camera_path, light_path, Some(&camera), s, 1, true);
+    splats.add(x as u32, height - 1 - y as u32, qs.beta * f * camera.beta * transmittance * weight);
+}
+
+// The power heuristic weight of joining `s` light vertices to `t` camera vertices
+// against every other strategy which could have made the same path. `sampled` stands in
+// for the last light vertex when s is one, or for the camera when t is one.
+fn mis_weight(
+    config: &Config,
+    camera_path: &[Vertex],
+    light_path: &[Vertex],
+    sampled: Option<&Vertex>,
+    s: usize,
+    t: usize,
+    light_tracing: bool,
+) -> f64 {
+    if s + t == 2 {
+        return 1.0;
+    }
+
+    let pt = match (t, sampled) {
+        (1, Some(v)) => v,
+        _ => &camera_path[t - 1],
+    };
+    let qs = match (s, sampled) {
+        (0, _) => None,
+        (1, Some(v)) => Some(v),
+        _ => Some(&light_path[s - 1]),
+    };
+    let pt_minus = if t > 1 { Some(&camera_path[t - 2]) } else { None };
+    let qs_minus = if s > 1 { Some(&light_path[s - 2]) } else { None };
+
+    // The forward and reverse densities and specularity of each vertex, as if this
+    // strategy had made the path. The two joined vertices are never specular.
+    let densities = |v: &Vertex| (v.pdf_fwd, v.pdf_rev, v.delta);
+    let mut camera: Vec<(f64, f64, bool)> = camera_path[..t].iter().map(densities).collect();
+    let mut light: Vec<(f64, f64, bool)> = light_path[..s].iter().map(densities).collect();
+
+    let pt_rev = match qs {
+        Some(qs) => qs.pdf(config, qs_minus, pt),
+        None => pdf_light_origin(config),
+    };
+    camera[t - 1] = (pt.pdf_fwd, pt_rev, false);
+
+    if let Some(pt_minus) = pt_minus {
+        camera[t - 2].1 = match qs {
+            Some(qs) => pt.pdf(config, Some(qs), pt_minus),
+            None => pt.pdf_light(pt_minus),
+        };
+    }
+
+    if let Some(qs) = qs {
+        light[s - 1] = (qs.pdf_fwd, pt.pdf(config, pt_minus, qs), false);
+
+        if let Some(qs_minus) = qs_minus {
+            light[s - 2].1 = qs.pdf(config, Some(pt), qs_minus);
+        }
+    }
+
+    // Specular vertices have no density, which cancels out of the ratios
+    let remap = |pdf: f64| if pdf != 0.0 { pdf } else { 1.0 };
+
+    // Each step moves one more vertex to the other subpath, giving the ratio between the
+    // density of that strategy and this one
+    let mut sum = 0.0;
+    let mut ratio = 1.0;
+    for i in (1..t).rev() {
+        ratio *= remap(camera[i].1) / remap(camera[i].0);
+        if !camera[i].2 && !camera[i - 1].2 && (light_tracing || i > 1) {
+            sum += ratio * ratio;
+        }
+    }
+
+    ratio = 1.0;
+    for i in (0..s).rev() {
+        ratio *= remap(light[i].1) / remap(light[i].0);
+        if !light[i].2 && (i == 0 || !light[i - 1].2) {
+            sum += ratio * ratio;
+        }
+    }
+
+    1.0 / (1.0 + sum)
+}
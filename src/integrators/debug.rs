@@ -0,0 +1,89 @@
+use crate::{
+    graphics::{Hittable, Ray},
+    math::Color,
+    utils::Config,
+};
+
+use super::Integrator;
+
+// What a debug integrator shows about the first surface each ray hits
+#[derive(Clone, Copy, Debug)]
+pub enum DebugView {
+    // Outward facing normals mapped from [-1, 1] to [0, 1]
+    Normals,
+    // Texture coordinates in the red and green channels
+    Uvs,
+    // Distance from the camera divided by the given distance, so it stays linear in HDR output
+    Depth(f64),
+    // The attenuation of a single scatter, or the emission of a light
+    Albedo,
+    // A colour made from the identity of the material, so surfaces sharing one match
+    MaterialId,
+}
+
+// Shows properties of the scene rather than its lighting. Misses are always black.
+pub struct DebugIntegrator {
+    view: DebugView,
+}
+
+impl DebugIntegrator {
+    pub fn new(view: DebugView) -> Self {
+        DebugIntegrator { view }
+    }
+
+    // Scatters the bits of an address so that nearby materials get very different colours
+    fn id_color(id: usize) -> Color {
+        let mut x = id as u64;
+        x ^= x >> 33;
+        x = x.wrapping_mul(0xff51afd7ed558ccd);
+        x ^= x >> 33;
+        x = x.wrapping_mul(0xc4ceb9fe1a85ec53);
+        x ^= x >> 33;
+
+        let channel = |shift: u64| ((x >> shift) & 0xff) as f64 / 255.0;
+        Color::new(channel(0), channel(8), channel(16))
+    }
+}
+
+impl Integrator for DebugIntegrator {
+    fn radiance(&self, ray: Ray, config: &Config) -> Color {
+        let hit = match config.world.hit(&ray, 0.001, f64::INFINITY) {
+            Some(h) => h,
+            None => return Color::ZERO,
+        };
+
+        match self.view {
+            DebugView::Normals => {
+                let outward_normal = if hit.front_face { hit.normal } else { -hit.normal };
+                (outward_normal + 1.0) * 0.5
+            }
+            DebugView::Uvs => Color::new(hit.u, hit.v, 0.0),
+            DebugView::Depth(max_distance) => Color::ONE * (hit.t / max_distance),
+            DebugView::Albedo => match hit.material.scatter(&ray, &hit) {
+                Some((_, attenuation)) => attenuation,
+                None => hit.material.emitted(hit.u, hit.v, hit.p),
+            },
+            DebugView::MaterialId => DebugIntegrator::id_color(hit.material.id()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::graphics::materials::{Lambertian, Material};
+
+    use super::*;
+
+    #[test]
+    fn shared_materials_have_one_id() {
+        let shared: Arc<dyn Material> = Arc::new(Lambertian::new_from_color(Color::ONE));
+        let first: Box<dyn Material> = Box::new(shared.clone());
+        let second: Box<dyn Material> = Box::new(shared);
+        let separate: Box<dyn Material> = Box::new(Lambertian::new_from_color(Color::ONE));
+
+        assert_eq!(first.id(), second.id());
+        assert_ne!(first.id(), separate.id());
+    }
+}
@@ -0,0 +1,21 @@
+use crate::{graphics::Ray, math::Color, output::SplatBuffer, utils::Config};
+
+// Works out the light arriving at the camera along a ray through the scene
+pub trait Integrator: Sync + Send {
+    fn radiance(&self, ray: Ray, config: &Config) -> Color;
+
+    // Like `radiance`, but may also add light to any pixel through `splats`, as light
+    // tracing does. Splats are averaged over the samples per pixel along with everything else.
+    fn sample(&self, ray: Ray, config: &Config, _splats: &SplatBuffer) -> Color {
+        self.radiance(ray, config)
+    }
+
+    // How many times `render_image` goes over the image, splitting the samples per pixel
+    // between them and averaging the results
+    fn passes(&self, _config: &Config) -> u32 {
+        1
+    }
+
+    // Called before each pass, for integrators that build something from the scene first
+    fn begin_pass(&self, _pass: u32, _config: &Config) {}
+}
@@ -0,0 +1,151 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::{
+    graphics::{HitRecord, Hittable, Ray, volumes::AtmosphereEvent},
+    math::{Color, Vec3},
+    utils::Config,
+};
+
+// Weights a sample from one strategy against another that could have found the same
+// light, favouring whichever was more likely to
+pub fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0.0 {
+        0.0
+    } else {
+        a / (a + b)
+    }
+}
+
+// How much of the light from the end of a shadow ray makes it through the atmosphere and
+// the media which shadow rays see through
+pub fn shadow_transmittance(ray: &Ray, t_max: f64, config: &Config) -> Color {
+    let media = config.world.transmittance(ray, 0.001, t_max);
+    if media <= 0.0 {
+        return Color::ZERO;
+    }
+
+    match &config.atmosphere {
+        None => Color::ONE * media,
+        Some(atmosphere) => match atmosphere.sample(ray, 0.001, t_max) {
+            AtmosphereEvent::Scattered(..) => Color::ZERO,
+            AtmosphereEvent::Transmitted(w) => w * media,
+        },
+    }
+}
+
+// The factors of one sample of direct light, kept apart so spectral integrators can turn
+// each colour into a spectrum before multiplying them
+pub struct LightSample {
+    pub f: Color,
+    pub emitted: Color,
+    pub transmittance: Color,
+    pub weight: f64,
+}
+
+impl LightSample {
+    pub fn value(&self) -> Color {
+        self.f * self.emitted * self.transmittance * self.weight
+    }
+}
+
+// Light reaching a hit directly from the scene's lights and from the background, each
+// sampled once. With `mis` the samples are weighted against finding the same light by
+// scattering, for integrators which also do that.
+pub fn sample_lights(ray: &Ray, hit: &HitRecord, config: &Config, mis: bool) -> Color {
+    let direct = light_sample(ray, hit, config, mis).map_or(Color::ZERO, |s| s.value());
+    direct + sample_background(ray, hit, config, mis)
+}
+
+// Light reaching a hit directly from the background, sampled once, and weighted like
+// `sample_lights` with `mis`
+pub fn sample_background(ray: &Ray, hit: &HitRecord, config: &Config, mis: bool) -> Color {
+    background_sample(ray, hit, config, mis).map_or(Color::ZERO, |s| s.value())
+}
+
+// The sample of the scene's lights taken by `sample_lights`
+pub fn light_sample(ray: &Ray, hit: &HitRecord, config: &Config, mis: bool) -> Option<LightSample> {
+    if config.lights.is_empty() {
+        return None;
+    }
+
+    let material = hit.material;
+    let light_ray = Ray::new(hit.p, config.lights.random(hit.p), ray.time);
+    let light_pdf = config.lights.pdf_value(hit.p, light_ray.direction);
+    let f = material.eval(ray, hit, &light_ray);
+
+    if light_pdf <= 0.0 || f.near_zero() {
+        return None;
+    }
+
+    let light_hit = config.world.hit_surface(&light_ray, 0.001, f64::INFINITY)?;
+    let weight = if mis {
+        power_heuristic(light_pdf, material.scattering_pdf(ray, hit, &light_ray))
+    } else {
+        1.0
+    };
+
+    Some(LightSample {
+        f,
+        emitted: light_hit.material.emitted(light_hit.u, light_hit.v, light_hit.p),
+        transmittance: shadow_transmittance(&light_ray, light_hit.t, config),
+        weight: weight / light_pdf,
+    })
+}
+
+// The sample of the background taken by `sample_background`
+pub fn background_sample(ray: &Ray, hit: &HitRecord, config: &Config, mis: bool) -> Option<LightSample> {
+    let (direction, background_pdf) = config.background.sample_direction()?;
+
+    let material = hit.material;
+    let background_ray = Ray::new(hit.p, direction, ray.time);
+    let f = material.eval(ray, hit, &background_ray);
+
+    if background_pdf <= 0.0 || f.near_zero() || config.world.hit_surface(&background_ray, 0.001, f64::INFINITY).is_some() {
+        return None;
+    }
+
+    let weight = if mis {
+        power_heuristic(background_pdf, material.scattering_pdf(ray, hit, &background_ray))
+    } else {
+        1.0
+    };
+
+    Some(LightSample {
+        f,
+        emitted: config.background.value(direction),
+        transmittance: shadow_transmittance(&background_ray, f64::INFINITY, config),
+        weight: weight / background_pdf,
+    })
+}
+
+// Whether `ray` reaches one of the scene's lights at `t`, rather than some other emitter
+pub fn on_light(config: &Config, ray: &Ray, t: f64) -> bool {
+    config
+        .lights
+        .hit(ray, 0.001, f64::INFINITY)
+        .is_some_and(|h| (h.t - t).abs() < 1e-6)
+}
+
+// Diffuse lights shine from both sides, so emitted directions are cosine weighted about
+// either side of the surface
+pub fn emission_pdf(normal: Vec3, direction: Vec3) -> f64 {
+    normal.dot(direction).abs() / (2.0 * PI)
+}
+
+// A point picked uniformly over the scene's lights and a ray leaving it, for tracing light
+// into the scene. The densities are 1 / `config.lights.area()` and `emission_pdf`.
+pub fn sample_emission(config: &Config, time: f64) -> Option<(HitRecord<'_>, Ray)> {
+    let hit = config.lights.sample_surface()?;
+
+    let side = if rand::thread_rng().gen::<bool>() { hit.normal } else { -hit.normal };
+    let mut direction = side + Vec3::random_unit_vector();
+    if direction.near_zero() {
+        direction = side;
+    }
+
+    let ray = Ray::new(hit.p, direction, time);
+    Some((hit, ray))
+}
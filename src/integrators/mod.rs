@@ -0,0 +1,21 @@
+mod integrator;
+
+mod ambient_occlusion;
+mod bidirectional_path_tracer;
+mod debug;
+mod lighting;
+mod path_tracer;
+mod photon_map;
+mod photon_mapper;
+mod spectral_path_tracer;
+mod whitted;
+
+pub use integrator::Integrator;
+
+pub use ambient_occlusion::AmbientOcclusion;
+pub use bidirectional_path_tracer::BidirectionalPathTracer;
+pub use debug::{DebugIntegrator, DebugView};
+pub use path_tracer::PathTracer;
+pub use photon_mapper::PhotonMapper;
+pub use spectral_path_tracer::SpectralPathTracer;
+pub use whitted::Whitted;
@@ -0,0 +1,100 @@
+use rand::Rng;
+
+use crate::{
+    graphics::{Hittable, Ray, volumes::AtmosphereEvent},
+    math::Color,
+    utils::Config,
+};
+
+use super::{Integrator, lighting::{power_heuristic, sample_lights}};
+
+// Follows a path from the camera, adding up the light found at each bounce weighted by
+// the throughput of the path so far. Light found by scattering is weighted against
+// light sampling, unless the previous bounce was specular or this is the camera ray,
+// as nothing could have sampled it then.
+pub struct PathTracer;
+
+impl PathTracer {
+    pub fn new() -> Self {
+        PathTracer
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, ray: Ray, config: &Config) -> Color {
+        let mut rng = rand::thread_rng();
+
+        let mut ray = ray;
+        let mut radiance = Color::ZERO;
+        let mut throughput = Color::ONE;
+        let mut scattering_pdf: Option<f64> = None;
+
+        for depth in 0..config.max_depth {
+            let mut hit = config.world.hit(&ray, 0.001, f64::INFINITY);
+
+            // The atmosphere may scatter the ray before it reaches whatever it was heading for
+            if let Some(atmosphere) = &config.atmosphere {
+                let t_max = hit.as_ref().map_or(f64::INFINITY, |h| h.t);
+                match atmosphere.sample(&ray, 0.001, t_max) {
+                    AtmosphereEvent::Scattered(medium_hit, w) => {
+                        hit = Some(medium_hit);
+                        throughput = throughput * w;
+                    }
+                    AtmosphereEvent::Transmitted(w) => throughput = throughput * w,
+                }
+            }
+
+            let hit = match hit {
+                Some(h) => h,
+                None => {
+                    let mut background = config.background.value(ray.direction);
+                    if let Some(pdf) = scattering_pdf {
+                        background = background * power_heuristic(pdf, config.background.pdf(ray.direction));
+                    }
+                    radiance = radiance + throughput * background;
+                    break;
+                }
+            };
+
+            let mut emitted = hit.material.emitted(hit.u, hit.v, hit.p);
+            if let Some(pdf) = scattering_pdf {
+                emitted = emitted * power_heuristic(pdf, config.lights.pdf_value(ray.origin, ray.direction));
+            }
+            radiance = radiance + throughput * emitted;
+
+            let (scattered_ray, attenuation) = match hit.material.scatter(&ray, &hit) {
+                Some(s) => s,
+                None => break,
+            };
+
+            let pdf = hit.material.scattering_pdf(&ray, &hit, &scattered_ray);
+            if pdf > 0.0 {
+                radiance = radiance + throughput * sample_lights(&ray, &hit, config, true);
+                scattering_pdf = Some(pdf);
+            } else {
+                scattering_pdf = None;
+            }
+
+            throughput = throughput * attenuation;
+            ray = scattered_ray;
+
+            // Past the minimum depth, end paths at random in proportion to how little light
+            // they can still carry, and boost the survivors so the result stays unbiased
+            if depth + 1 >= config.russian_roulette_depth {
+                let survival = throughput.x.max(throughput.y).max(throughput.z).min(1.0);
+                if rng.gen::<f64>() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+
+        radiance
+    }
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        PathTracer::new()
+    }
+}
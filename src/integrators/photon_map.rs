@@ -0,0 +1,92 @@
+use crate::math::{Axis, Color, Point, Vec3};
+
+// A bundle of light from the lights, stored where it landed on a diffuse surface. The
+// direction is the one it was travelling in and the normal faces the side it arrived on.
+pub struct Photon {
+    pub position: Point,
+    pub direction: Vec3,
+    pub normal: Vec3,
+    pub power: Color,
+}
+
+// Photons kept as a balanced kd-tree laid out in a single array. Every range of the array
+// is split at its middle photon, with the photons before it on the low side of the axis
+// stored for that index and those after it on the high side.
+pub struct PhotonMap {
+    photons: Vec<Photon>,
+    axes: Vec<Axis>,
+}
+
+impl PhotonMap {
+    pub fn new(mut photons: Vec<Photon>) -> Self {
+        let mut axes = vec![Axis::X; photons.len()];
+        build(&mut photons, &mut axes);
+
+        PhotonMap { photons, axes }
+    }
+
+    pub fn empty() -> Self {
+        PhotonMap::new(Vec::new())
+    }
+
+    // Calls `f` with every photon within `radius` of `p`
+    pub fn for_each_near<F: FnMut(&Photon)>(&self, p: Point, radius: f64, mut f: F) {
+        self.search(0, self.photons.len(), p, radius * radius, &mut f);
+    }
+
+    fn search<F: FnMut(&Photon)>(&self, lo: usize, hi: usize, p: Point, radius_squared: f64, f: &mut F) {
+        if lo >= hi {
+            return;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+        let photon = &self.photons[mid];
+        if (photon.position - p).length_squared() <= radius_squared {
+            f(photon);
+        }
+
+        // Look on the side of the split holding `p` first, and only cross over if the
+        // search sphere reaches the other side
+        let axis = &self.axes[mid];
+        let offset = p[axis] - photon.position[axis];
+        let (near, far) = if offset < 0.0 { ((lo, mid), (mid + 1, hi)) } else { ((mid + 1, hi), (lo, mid)) };
+
+        self.search(near.0, near.1, p, radius_squared, f);
+        if offset * offset <= radius_squared {
+            self.search(far.0, far.1, p, radius_squared, f);
+        }
+    }
+}
+
+// Splits each range along the axis it is widest in, at the median photon
+fn build(photons: &mut [Photon], axes: &mut [Axis]) {
+    if photons.len() <= 1 {
+        return;
+    }
+
+    let mut minimum = photons[0].position;
+    let mut maximum = photons[0].position;
+    for photon in photons.iter() {
+        let p = photon.position;
+        minimum = Vec3::new(minimum.x.min(p.x), minimum.y.min(p.y), minimum.z.min(p.z));
+        maximum = Vec3::new(maximum.x.max(p.x), maximum.y.max(p.y), maximum.z.max(p.z));
+    }
+
+    let extent = maximum - minimum;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z {
+        Axis::X
+    } else if extent.y >= extent.z {
+        Axis::Y
+    } else {
+        Axis::Z
+    };
+
+    let mid = photons.len() / 2;
+    photons.select_nth_unstable_by(mid, |a, b| a.position[&axis].total_cmp(&b.position[&axis]));
+    axes[mid] = axis;
+
+    let (low, rest) = photons.split_at_mut(mid);
+    let (low_axes, rest_axes) = axes.split_at_mut(mid);
+    build(low, low_axes);
+    build(&mut rest[1..], &mut rest_axes[1..]);
+}
@@ -0,0 +1,298 @@
+use std::{f64::consts::PI, sync::RwLock};
+
+use rand::Rng;
+use rayon::prelude::*;
+
+use crate::{
+    graphics::{Aabb, HitRecord, Hittable, Ray, volumes::AtmosphereEvent},
+    math::{Color, Onb, Vec3},
+    utils::Config,
+};
+
+use super::{
+    Integrator,
+    lighting::{on_light, power_heuristic, sample_emission, sample_lights},
+    photon_map::{Photon, PhotonMap},
+};
+
+// Path tracing with caustics taken from a photon map instead. Photons leave the lights, or
+// the background in scenes without any, and are stored at the first diffuse surface they
+// reach after going through glass or off metal. Camera paths estimate the density of
+// photons around each diffuse surface they hit, and leave out the light that the photons
+// already carried there.
+//
+// Progressive photon mapping traces a new set of photons for each of several passes, with
+// a radius that shrinks a little each time, so the blur of the estimate fades away as the
+// passes are averaged.
+pub struct PhotonMapper {
+    photon_count: usize,
+    radius: f64,
+    iterations: u32,
+    alpha: f64,
+    focus: Option<Aabb>,
+    pass: RwLock<PhotonPass>,
+}
+
+struct PhotonPass {
+    photons: PhotonMap,
+    radius: f64,
+}
+
+impl PhotonMapper {
+    // `photon_count` photons are traced for each pass, and gathered from within `radius`
+    // of each shading point
+    pub fn new(photon_count: usize, radius: f64) -> Self {
+        PhotonMapper {
+            photon_count,
+            radius,
+            iterations: 1,
+            alpha: 1.0,
+            focus: None,
+            pass: RwLock::new(PhotonPass {
+                photons: PhotonMap::empty(),
+                radius,
+            }),
+        }
+    }
+
+    // Spreads the samples per pixel over `iterations` passes. Each pass keeps `alpha` of
+    // the photons gathered by the last one while shrinking the radius, so smaller values
+    // shrink it faster at the cost of more noise. 0.7 is a good default.
+    pub fn set_progressive(self, iterations: u32, alpha: f64) -> Self {
+        PhotonMapper {
+            iterations: iterations.max(1),
+            alpha,
+            ..self
+        }
+    }
+
+    // The part of the scene to aim photons from the background at. By default it is the
+    // whole world, which wastes most photons when there is a large ground.
+    pub fn set_focus(self, focus: Aabb) -> Self {
+        PhotonMapper {
+            focus: Some(focus),
+            ..self
+        }
+    }
+
+    // r²ᵢ₊₁ = r²ᵢ (i + α) / (i + 1)
+    fn pass_radius(&self, pass: u32) -> f64 {
+        let mut radius_squared = self.radius * self.radius;
+        for i in 1..=pass {
+            radius_squared *= (i as f64 + self.alpha) / (i as f64 + 1.0);
+        }
+        radius_squared.sqrt()
+    }
+
+    // A photon leaving the lights, or the background if there are none, with its share of
+    // the light they give out
+    fn emit(&self, config: &Config, bounds: Option<Aabb>) -> Option<(Ray, Color)> {
+        let time = config.camera.sample_time();
+        let count = self.photon_count as f64;
+
+        if !config.lights.is_empty() {
+            let (hit, ray) = sample_emission(config, time)?;
+            let emitted = hit.material.emitted(hit.u, hit.v, hit.p);
+            return Some((ray, emitted * (config.lights.area() * 2.0 * PI / count)));
+        }
+
+        // Light from the background arrives as if from infinitely far away, so it is
+        // emitted from a disk facing the direction it comes from, which covers the bounds
+        let bounds = bounds?;
+        let (direction, pdf) = config
+            .background
+            .sample_direction()
+            .unwrap_or_else(|| (Vec3::random_unit_vector(), 1.0 / (4.0 * PI)));
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        let center = (bounds.minimum + bounds.maximum) / 2.0;
+        let radius = (bounds.maximum - bounds.minimum).length() / 2.0;
+        let onb = Onb::new_from_w(direction);
+        let d = Vec3::random_in_unit_disk();
+        let origin = center + radius * (direction + d.x * onb.u + d.y * onb.v);
+
+        let power = config.background.value(direction) * (PI * radius * radius / (pdf * count));
+        Some((Ray::new(origin, -direction, time), power))
+    }
+
+    // Follows a photon through specular bounces, returning it if it then lands on a
+    // diffuse surface
+    fn trace_photon(&self, config: &Config, bounds: Option<Aabb>) -> Option<Photon> {
+        let mut rng = rand::thread_rng();
+        let (mut ray, mut power) = self.emit(config, bounds)?;
+        let mut throughput = Color::ONE;
+
+        for depth in 0..config.max_depth {
+            let hit = config.world.hit(&ray, 0.001, f64::INFINITY);
+
+            // Photons scattered by the atmosphere are lost to the caustics
+            if let Some(atmosphere) = &config.atmosphere {
+                let t_max = hit.as_ref().map_or(f64::INFINITY, |h| h.t);
+                match atmosphere.sample(&ray, 0.001, t_max) {
+                    AtmosphereEvent::Scattered(..) => return None,
+                    AtmosphereEvent::Transmitted(w) => power = power * w,
+                }
+            }
+
+            let hit = hit?;
+            if hit.material.is_volumetric() {
+                return None;
+            }
+
+            let (scattered_ray, attenuation) = hit.material.scatter(&ray, &hit)?;
+            if hit.material.scattering_pdf(&ray, &hit, &scattered_ray) > 0.0 {
+                return if depth > 0 {
+                    Some(Photon {
+                        position: hit.p,
+                        direction: ray.direction,
+                        normal: hit.normal,
+                        power,
+                    })
+                } else {
+                    None
+                };
+            }
+
+            power = power * attenuation;
+            throughput = throughput * attenuation;
+            ray = scattered_ray;
+
+            if depth + 1 >= config.russian_roulette_depth {
+                let survival = throughput.x.max(throughput.y).max(throughput.z).min(1.0);
+                if rng.gen::<f64>() >= survival {
+                    return None;
+                }
+                power = power / survival;
+                throughput = throughput / survival;
+            }
+        }
+
+        None
+    }
+
+    // The light reflected back along `ray` by the photons around `hit`
+    fn estimate(&self, ray: &Ray, hit: &HitRecord, pass: &PhotonPass) -> Color {
+        let mut reflected = Color::ZERO;
+        pass.photons.for_each_near(hit.p, pass.radius, |photon| {
+            // Skip photons from other surfaces close by, or from the back of this one
+            if photon.normal.dot(hit.normal) < 0.5 {
+                return;
+            }
+
+            let incoming = Ray::new(hit.p, -photon.direction, ray.time);
+            let cos = hit.normal.dot(incoming.direction);
+            if cos > 0.0 {
+                reflected = reflected + hit.material.eval(ray, hit, &incoming) * photon.power / cos;
+            }
+        });
+
+        reflected / (PI * pass.radius * pass.radius)
+    }
+}
+
+impl Integrator for PhotonMapper {
+    fn radiance(&self, ray: Ray, config: &Config) -> Color {
+        let pass = self.pass.read().unwrap();
+        let mut rng = rand::thread_rng();
+
+        let mut ray = ray;
+        let mut radiance = Color::ZERO;
+        let mut throughput = Color::ONE;
+        let mut scattering_pdf: Option<f64> = None;
+
+        // Whether the last bounce which wasn't specular was off a surface, where photons were
+        // gathered. Light which only bounced specularly since then came the same way as the
+        // photons, so it has already been counted.
+        let mut after_gather = false;
+
+        for depth in 0..config.max_depth {
+            let mut hit = config.world.hit(&ray, 0.001, f64::INFINITY);
+
+            if let Some(atmosphere) = &config.atmosphere {
+                let t_max = hit.as_ref().map_or(f64::INFINITY, |h| h.t);
+                match atmosphere.sample(&ray, 0.001, t_max) {
+                    AtmosphereEvent::Scattered(medium_hit, w) => {
+                        hit = Some(medium_hit);
+                        throughput = throughput * w;
+                    }
+                    AtmosphereEvent::Transmitted(w) => throughput = throughput * w,
+                }
+            }
+
+            let caustic = after_gather && scattering_pdf.is_none();
+
+            let hit = match hit {
+                Some(h) => h,
+                None => {
+                    if caustic && config.lights.is_empty() {
+                        break;
+                    }
+                    let mut background = config.background.value(ray.direction);
+                    if let Some(pdf) = scattering_pdf {
+                        background = background * power_heuristic(pdf, config.background.pdf(ray.direction));
+                    }
+                    radiance = radiance + throughput * background;
+                    break;
+                }
+            };
+
+            if !(caustic && on_light(config, &ray, hit.t)) {
+                let mut emitted = hit.material.emitted(hit.u, hit.v, hit.p);
+                if let Some(pdf) = scattering_pdf {
+                    emitted = emitted * power_heuristic(pdf, config.lights.pdf_value(ray.origin, ray.direction));
+                }
+                radiance = radiance + throughput * emitted;
+            }
+
+            let (scattered_ray, attenuation) = match hit.material.scatter(&ray, &hit) {
+                Some(s) => s,
+                None => break,
+            };
+
+            let pdf = hit.material.scattering_pdf(&ray, &hit, &scattered_ray);
+            if pdf > 0.0 {
+                radiance = radiance + throughput * sample_lights(&ray, &hit, config, true);
+                scattering_pdf = Some(pdf);
+
+                after_gather = !hit.material.is_volumetric();
+                if after_gather {
+                    radiance = radiance + throughput * self.estimate(&ray, &hit, &pass);
+                }
+            } else {
+                scattering_pdf = None;
+            }
+
+            throughput = throughput * attenuation;
+            ray = scattered_ray;
+
+            if depth + 1 >= config.russian_roulette_depth {
+                let survival = throughput.x.max(throughput.y).max(throughput.z).min(1.0);
+                if rng.gen::<f64>() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+
+        radiance
+    }
+
+    fn passes(&self, _config: &Config) -> u32 {
+        self.iterations
+    }
+
+    fn begin_pass(&self, pass: u32, config: &Config) {
+        let bounds = self.focus.or_else(|| config.world.bounding_box(0.0, 1.0));
+        let photons = (0..self.photon_count)
+            .into_par_iter()
+            .filter_map(|_| self.trace_photon(config, bounds))
+            .collect();
+
+        *self.pass.write().unwrap() = PhotonPass {
+            photons: PhotonMap::new(photons),
+            radius: self.pass_radius(pass),
+        };
+    }
+}
@@ -0,0 +1,120 @@
+use rand::Rng;
+
+use crate::{
+    graphics::{Hittable, Ray, volumes::AtmosphereEvent},
+    math::{Color, SampledSpectrum, SampledWavelengths, flat_spectrum_rgb, xyz_to_rgb},
+    utils::Config,
+};
+
+use super::{Integrator, lighting::{LightSample, background_sample, light_sample, power_heuristic}};
+
+// Path tracing over wavelengths instead of RGB, so dispersive glass can split light into
+// colours. Each path carries a hero wavelength and a few more spaced evenly across the
+// visible range, sharing the path until it goes through something dispersive, after which
+// only the hero is kept. The RGB colours of materials, lights and the background are turned
+// into spectra along the way, and the result is turned back into RGB for the film.
+pub struct SpectralPathTracer {
+    white: Color,
+}
+
+impl SpectralPathTracer {
+    pub fn new() -> Self {
+        SpectralPathTracer {
+            white: flat_spectrum_rgb(),
+        }
+    }
+}
+
+fn light_spectrum(sample: &LightSample, wavelengths: &SampledWavelengths) -> SampledSpectrum {
+    SampledSpectrum::from_rgb(sample.f, wavelengths)
+        * SampledSpectrum::from_rgb(sample.emitted, wavelengths)
+        * SampledSpectrum::from_rgb(sample.transmittance, wavelengths)
+        * sample.weight
+}
+
+impl Integrator for SpectralPathTracer {
+    fn radiance(&self, ray: Ray, config: &Config) -> Color {
+        let mut rng = rand::thread_rng();
+        let mut wavelengths = SampledWavelengths::sample_visible(rng.gen());
+        let spectrum = SampledSpectrum::from_rgb;
+
+        let mut ray = ray;
+        ray.wavelength = Some(wavelengths.hero());
+        let mut radiance = SampledSpectrum::ZERO;
+        let mut throughput = SampledSpectrum::ONE;
+        let mut scattering_pdf: Option<f64> = None;
+
+        for depth in 0..config.max_depth {
+            let mut hit = config.world.hit(&ray, 0.001, f64::INFINITY);
+
+            if let Some(atmosphere) = &config.atmosphere {
+                let t_max = hit.as_ref().map_or(f64::INFINITY, |h| h.t);
+                match atmosphere.sample(&ray, 0.001, t_max) {
+                    AtmosphereEvent::Scattered(medium_hit, w) => {
+                        hit = Some(medium_hit);
+                        throughput = throughput * spectrum(w, &wavelengths);
+                    }
+                    AtmosphereEvent::Transmitted(w) => throughput = throughput * spectrum(w, &wavelengths),
+                }
+            }
+
+            let hit = match hit {
+                Some(h) => h,
+                None => {
+                    let mut background = spectrum(config.background.value(ray.direction), &wavelengths);
+                    if let Some(pdf) = scattering_pdf {
+                        background = background * power_heuristic(pdf, config.background.pdf(ray.direction));
+                    }
+                    radiance = radiance + throughput * background;
+                    break;
+                }
+            };
+
+            let mut emitted = spectrum(hit.material.emitted(hit.u, hit.v, hit.p), &wavelengths);
+            if let Some(pdf) = scattering_pdf {
+                emitted = emitted * power_heuristic(pdf, config.lights.pdf_value(ray.origin, ray.direction));
+            }
+            radiance = radiance + throughput * emitted;
+
+            let (mut scattered_ray, attenuation) = match hit.material.scatter(&ray, &hit) {
+                Some(s) => s,
+                None => break,
+            };
+
+            if hit.material.is_dispersive() {
+                wavelengths.terminate_secondary();
+            }
+
+            let pdf = hit.material.scattering_pdf(&ray, &hit, &scattered_ray);
+            if pdf > 0.0 {
+                let samples = light_sample(&ray, &hit, config, true).into_iter().chain(background_sample(&ray, &hit, config, true));
+                for sample in samples {
+                    radiance = radiance + throughput * light_spectrum(&sample, &wavelengths);
+                }
+                scattering_pdf = Some(pdf);
+            } else {
+                scattering_pdf = None;
+            }
+
+            throughput = throughput * spectrum(attenuation, &wavelengths);
+            scattered_ray.wavelength = Some(wavelengths.hero());
+            ray = scattered_ray;
+
+            if depth + 1 >= config.russian_roulette_depth {
+                let survival = throughput.max_value().min(1.0);
+                if rng.gen::<f64>() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+
+        xyz_to_rgb(wavelengths.to_xyz(&radiance)) / self.white
+    }
+}
+
+impl Default for SpectralPathTracer {
+    fn default() -> Self {
+        SpectralPathTracer::new()
+    }
+}
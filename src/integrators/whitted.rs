@@ -0,0 +1,59 @@
+use crate::{
+    graphics::{Hittable, Ray},
+    math::Color,
+    utils::Config,
+};
+
+use super::{Integrator, lighting::sample_lights};
+
+// Follows mirror reflections and refractions, but only lights diffuse surfaces directly
+// from the scene's lights and background. Much less noisy than path tracing, but misses
+// indirect light, so a scene needs sampleable lights to show anything diffuse.
+pub struct Whitted;
+
+impl Whitted {
+    pub fn new() -> Self {
+        Whitted
+    }
+}
+
+impl Integrator for Whitted {
+    fn radiance(&self, ray: Ray, config: &Config) -> Color {
+        let mut ray = ray;
+        let mut radiance = Color::ZERO;
+        let mut throughput = Color::ONE;
+
+        for _ in 0..config.max_depth {
+            let hit = match config.world.hit(&ray, 0.001, f64::INFINITY) {
+                Some(h) => h,
+                None => {
+                    radiance = radiance + throughput * config.background.value(ray.direction);
+                    break;
+                }
+            };
+
+            radiance = radiance + throughput * hit.material.emitted(hit.u, hit.v, hit.p);
+
+            let (scattered_ray, attenuation) = match hit.material.scatter(&ray, &hit) {
+                Some(s) => s,
+                None => break,
+            };
+
+            if hit.material.scattering_pdf(&ray, &hit, &scattered_ray) > 0.0 {
+                radiance = radiance + throughput * sample_lights(&ray, &hit, config, false);
+                break;
+            }
+
+            throughput = throughput * attenuation;
+            ray = scattered_ray;
+        }
+
+        radiance
+    }
+}
+
+impl Default for Whitted {
+    fn default() -> Self {
+        Whitted::new()
+    }
+}
@@ -1,67 +1,64 @@
 pub mod graphics;
+pub mod integrators;
+pub mod loaders;
 pub mod math;
+pub mod output;
 pub mod utils;
 
-use graphics::{Hittable, Ray};
-
 use math::Color;
+use output::{Framebuffer, SplatBuffer};
 
 use indicatif::ProgressBar;
 use rand::Rng;
 use rayon::prelude::*;
 use utils::Config;
 
-fn ray_color<T: Hittable + Sync + Send>(ray: Ray, world: &T, depth: u32) -> Color {
-    if depth == 0 {
-        return Color::ZERO;
-    }
-
-    if let Some(hit) = world.hit(&ray, 0.001, f64::INFINITY) {
-        match hit.material.scatter(&ray, &hit) {
-            None => return Color::ZERO,
-            Some((scattered_ray, attenuation)) => {
-                return attenuation * ray_color(scattered_ray, world, depth - 1)
-            }
-        }
-    }
-
-    let unit_direction = ray.direction;
-    let t = 0.5 * (unit_direction.y + 1.0);
-    (1.0 - t) * Color::ONE + t * Color::new(0.5, 0.7, 1.0)
-}
-
-pub fn render_image(config: Config) {
+pub fn render_image(config: Config) -> Framebuffer {
     let image_width = config.image_size.0;
     let image_height = config.image_size.1;
 
-    let progress_bar = ProgressBar::new(image_height.into());
+    let passes = config.integrator.passes(&config).max(1);
+    let samples_per_pass = (config.samples_per_pixel / passes).max(1);
+    let total_samples = samples_per_pass * passes;
 
-    print!("P3\n{} {}\n255\n", image_width, image_height);
+    let progress_bar = ProgressBar::new(u64::from(image_height) * u64::from(passes));
+    let splats = SplatBuffer::new(image_width, image_height);
+    let mut pixel_sums = vec![Color::ZERO; (image_width * image_height) as usize];
 
-    let pixel_list = (0..(image_height * image_width))
-        .into_par_iter()
-        .map(|i| (i % image_width, image_height - i / image_width))
-        .map(|(i, j)| {
-            if i == 0 {
-                progress_bar.inc(1);
-            }
-            // Random number generator
-            let mut rng = rand::thread_rng();
-            let mut pixel_color = Color::ZERO;
-            for _ in 0..config.samples_per_pixel {
-                let u = ((i as f64) + rng.gen::<f64>()) / (image_width - 1) as f64;
-                let v = ((j as f64) + rng.gen::<f64>()) / (image_height - 1) as f64;
+    for pass in 0..passes {
+        config.integrator.begin_pass(pass, &config);
 
-                let ray = config.camera.get_ray(u, v);
-                pixel_color = pixel_color + ray_color(ray, &config.world, config.max_depth);
-            }
-            (pixel_color, i, j)
-        })
-        .collect::<Vec<(Color, u32, u32)>>();
+        pixel_sums
+            .par_iter_mut()
+            .enumerate()
+            .map(|(i, sum)| (sum, i as u32 % image_width, image_height - 1 - i as u32 / image_width))
+            .for_each(|(sum, i, j)| {
+                if i == 0 {
+                    progress_bar.inc(1);
+                }
+                // Random number generator
+                let mut rng = rand::thread_rng();
+                for _ in 0..samples_per_pass {
+                    let u = ((i as f64) + rng.gen::<f64>()) / (image_width - 1) as f64;
+                    let v = ((j as f64) + rng.gen::<f64>()) / (image_height - 1) as f64;
 
-    for pixel in pixel_list {
-        pixel.0.write_color(config.samples_per_pixel);
+                    let ray = config.camera.get_ray(u, v);
+                    *sum = *sum + config.integrator.sample(ray, &config, &splats);
+                }
+            });
     }
 
-    progress_bar.inc(1);
+    progress_bar.finish();
+
+    let pixel_list = pixel_sums
+        .into_iter()
+        .zip(splats.into_pixels())
+        .map(|(pixel, splat)| (pixel + splat) / total_samples)
+        .collect();
+
+    let mut framebuffer = Framebuffer::from_pixels(image_width, image_height, pixel_list);
+    framebuffer.set_tone_mapper(config.tone_mapper);
+    framebuffer.set_exposure(config.exposure);
+
+    framebuffer
 }
@@ -0,0 +1,57 @@
+use std::{error::Error, fmt, io, path::PathBuf};
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(PathBuf, io::Error),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    Binary {
+        path: PathBuf,
+        offset: usize,
+        message: String,
+    },
+}
+
+impl LoadError {
+    pub fn parse<T: Into<PathBuf>, M: Into<String>>(path: T, line: usize, message: M) -> Self {
+        LoadError::Parse {
+            path: path.into(),
+            line,
+            message: message.into(),
+        }
+    }
+
+    pub fn binary<T: Into<PathBuf>, M: Into<String>>(path: T, offset: usize, message: M) -> Self {
+        LoadError::Binary {
+            path: path.into(),
+            offset,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            LoadError::Parse { path, line, message } => {
+                write!(f, "{}:{}: {}", path.display(), line, message)
+            }
+            LoadError::Binary { path, offset, message } => {
+                write!(f, "{}: byte {}: {}", path.display(), offset, message)
+            }
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(_, e) => Some(e),
+            LoadError::Parse { .. } | LoadError::Binary { .. } => None,
+        }
+    }
+}
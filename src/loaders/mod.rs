@@ -0,0 +1,14 @@
+mod error;
+mod mtl;
+mod obj;
+mod parsing;
+mod ply;
+mod stl;
+mod vol;
+
+pub use error::LoadError;
+pub use mtl::load_mtl;
+pub use obj::load_obj;
+pub use ply::load_ply;
+pub use stl::load_stl;
+pub use vol::load_vol;
@@ -0,0 +1,177 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use crate::{
+    graphics::{
+        materials::{Conductor, Dielectric, Lambertian, Material},
+        textures::ImageTexture,
+    },
+    math::Color,
+};
+
+use super::{LoadError, parsing::parse_floats};
+
+// The subset of a Wavefront material description that maps onto our materials
+struct MtlDescription {
+    diffuse: Color,
+    specular: Color,
+    shininess: f64,
+    refractive_index: f64,
+    dissolve: f64,
+    diffuse_map: Option<String>,
+}
+
+impl MtlDescription {
+    fn new() -> Self {
+        MtlDescription {
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::ZERO,
+            shininess: 0.0,
+            refractive_index: 1.5,
+            dissolve: 1.0,
+            diffuse_map: None,
+        }
+    }
+
+    fn max_component(c: Color) -> f64 {
+        c.x.max(c.y).max(c.z)
+    }
+
+    // Converts the Phong exponent into a roughness, sharper highlights being smoother
+    fn roughness(&self) -> f64 {
+        (2.0 / (self.shininess + 2.0)).sqrt().clamp(0.0, 1.0)
+    }
+
+    // Transparent surfaces become glass, surfaces that are mostly specular become metal,
+    // and everything else is diffuse
+    fn into_material(self, directory: &Path) -> Arc<dyn Material> {
+        if self.dissolve < 1.0 {
+            return Arc::new(Dielectric::new(self.refractive_index));
+        }
+
+        if let Some(map) = self.diffuse_map {
+            let path = directory.join(map);
+            return Arc::new(Lambertian::new(Box::new(ImageTexture::new(&path.to_string_lossy()))));
+        }
+
+        if MtlDescription::max_component(self.specular) > MtlDescription::max_component(self.diffuse) {
+            return Arc::new(Conductor::new_from_color(self.specular, self.roughness()));
+        }
+
+        Arc::new(Lambertian::new_from_color(self.diffuse))
+    }
+}
+
+fn parse_color(path: &Path, line: usize, values: &[&str]) -> Result<Color, LoadError> {
+    // A single value is shorthand for a grey
+    if values.len() == 1 {
+        let v = parse_floats(path, line, values, 1)?;
+        return Ok(Color::new(v[0], v[0], v[0]));
+    }
+
+    let v = parse_floats(path, line, values, 3)?;
+    Ok(Color::new(v[0], v[1], v[2]))
+}
+
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<HashMap<String, Arc<dyn Material>>, LoadError> {
+    let path = path.as_ref();
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let contents = fs::read_to_string(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+
+    Ok(parse_mtl(path, &contents)?
+        .into_iter()
+        .map(|(name, description)| (name, description.into_material(directory)))
+        .collect())
+}
+
+fn parse_mtl(path: &Path, contents: &str) -> Result<HashMap<String, MtlDescription>, LoadError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlDescription)> = None;
+
+    for (index, raw_line) in contents.lines().enumerate() {
+        let line = index + 1;
+        let text = raw_line.split('#').next().unwrap_or("").trim();
+        let mut tokens = text.split_whitespace();
+
+        let keyword = match tokens.next() {
+            Some(k) => k,
+            None => continue,
+        };
+        let values: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if let Some((name, description)) = current.take() {
+                materials.insert(name, description);
+            }
+
+            if values.is_empty() {
+                return Err(LoadError::parse(path, line, "newmtl is missing a material name"));
+            }
+            current = Some((values.join(" "), MtlDescription::new()));
+            continue;
+        }
+
+        let description = match current.as_mut() {
+            Some((_, d)) => d,
+            None => return Err(LoadError::parse(path, line, format!("'{}' appears before any newmtl", keyword))),
+        };
+
+        match keyword {
+            "Kd" => description.diffuse = parse_color(path, line, &values)?,
+            "Ks" => description.specular = parse_color(path, line, &values)?,
+            "Ns" => description.shininess = parse_floats(path, line, &values, 1)?[0],
+            "Ni" => description.refractive_index = parse_floats(path, line, &values, 1)?[0],
+            "d" => description.dissolve = parse_floats(path, line, &values, 1)?[0],
+            "Tr" => description.dissolve = 1.0 - parse_floats(path, line, &values, 1)?[0],
+            "map_Kd" => {
+                // Texture options come before the file name, which is always last
+                match values.last() {
+                    Some(file) => description.diffuse_map = Some(file.to_string()),
+                    None => return Err(LoadError::parse(path, line, "map_Kd is missing a file name")),
+                }
+            }
+            // Everything else, such as illumination models and other maps, is ignored
+            _ => {}
+        }
+    }
+
+    if let Some((name, description)) = current.take() {
+        materials.insert(name, description);
+    }
+
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> HashMap<String, MtlDescription> {
+        parse_mtl(Path::new("test.mtl"), contents).unwrap()
+    }
+
+    #[test]
+    fn reads_colours_and_scalars() {
+        let materials = parse("# a comment\nnewmtl red paint\nKd 0.8 0.1 0.1\nKs 0.5\nNi 1.33\nTr 0.25\n");
+        let red = &materials["red paint"];
+        assert!((red.diffuse - Color::new(0.8, 0.1, 0.1)).near_zero());
+        assert!((red.specular - Color::new(0.5, 0.5, 0.5)).near_zero());
+        assert_eq!(red.refractive_index, 1.33);
+        assert_eq!(red.dissolve, 0.75);
+    }
+
+    #[test]
+    fn phong_exponents_map_to_roughness() {
+        let materials = parse("newmtl dull\nNs 0\nnewmtl shiny\nNs 198\nnewmtl mirror\nNs 1e9\n");
+        assert_eq!(materials["dull"].roughness(), 1.0);
+        assert!((materials["shiny"].roughness() - 0.1).abs() < 1e-12);
+        assert!(materials["mirror"].roughness() < 1e-3);
+    }
+
+    #[test]
+    fn statements_before_newmtl_are_errors() {
+        match parse_mtl(Path::new("test.mtl"), "\nKd 1 1 1\n") {
+            Err(LoadError::Parse { line, .. }) => assert_eq!(line, 2),
+            _ => panic!("expected a parse error"),
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use crate::{
+    graphics::{
+        Hittable,
+        materials::{Lambertian, Material},
+        models::TriangleMesh,
+    },
+    math::{Color, Point, Vec3},
+};
+
+use super::{LoadError, load_mtl, parsing::parse_floats};
+
+// One corner of a face, as zero based indices into the vertex data
+#[derive(Clone, Copy)]
+struct FaceVertex {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+// OBJ indices start at one, and negative indices count back from the latest element
+fn resolve_index(path: &Path, line: usize, text: &str, count: usize, kind: &str) -> Result<usize, LoadError> {
+    let index: i64 = text
+        .parse()
+        .map_err(|_| LoadError::parse(path, line, format!("invalid {} index '{}'", kind, text)))?;
+
+    let resolved = if index > 0 {
+        index - 1
+    } else if index < 0 {
+        count as i64 + index
+    } else {
+        return Err(LoadError::parse(path, line, format!("{} index cannot be zero", kind)));
+    };
+
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(LoadError::parse(
+            path,
+            line,
+            format!("{} index {} is out of range, only {} defined so far", kind, index, count),
+        ));
+    }
+
+    Ok(resolved as usize)
+}
+
+fn parse_face_vertex(
+    path: &Path,
+    line: usize,
+    text: &str,
+    counts: (usize, usize, usize),
+) -> Result<FaceVertex, LoadError> {
+    let mut parts = text.split('/');
+
+    let position = match parts.next() {
+        Some(p) if !p.is_empty() => resolve_index(path, line, p, counts.0, "vertex")?,
+        _ => return Err(LoadError::parse(path, line, format!("face vertex '{}' has no position", text))),
+    };
+    let uv = match parts.next() {
+        Some(t) if !t.is_empty() => Some(resolve_index(path, line, t, counts.1, "texture coordinate")?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(n) if !n.is_empty() => Some(resolve_index(path, line, n, counts.2, "normal")?),
+        _ => None,
+    };
+
+    Ok(FaceVertex {
+        position,
+        uv,
+        normal,
+    })
+}
+
+// Collects the faces sharing a material, and the same vertex attributes, into an indexed
+// mesh. OBJ indexes positions, texture coordinates and normals separately, so each
+// distinct combination becomes one mesh vertex.
+struct MeshBuilder {
+    material: Arc<dyn Material>,
+    vertex_map: HashMap<(usize, Option<usize>, Option<usize>), u32>,
+    positions: Vec<Point>,
+    uvs: Option<Vec<(f64, f64)>>,
+    normals: Option<Vec<Vec3>>,
+    indices: Vec<[u32; 3]>,
+}
+
+impl MeshBuilder {
+    fn new(material: Arc<dyn Material>, has_uvs: bool, has_normals: bool) -> Self {
+        MeshBuilder {
+            material,
+            vertex_map: HashMap::new(),
+            positions: Vec::new(),
+            uvs: if has_uvs { Some(Vec::new()) } else { None },
+            normals: if has_normals { Some(Vec::new()) } else { None },
+            indices: Vec::new(),
+        }
+    }
+
+    fn add_vertex(&mut self, corner: FaceVertex, positions: &[Point], uvs: &[(f64, f64)], normals: &[Vec3]) -> u32 {
+        let key = (corner.position, corner.uv, corner.normal);
+        if let Some(&index) = self.vertex_map.get(&key) {
+            return index;
+        }
+
+        let index = self.positions.len() as u32;
+        self.positions.push(positions[corner.position]);
+        if let (Some(mesh_uvs), Some(i)) = (self.uvs.as_mut(), corner.uv) {
+            mesh_uvs.push(uvs[i]);
+        }
+        if let (Some(mesh_normals), Some(i)) = (self.normals.as_mut(), corner.normal) {
+            mesh_normals.push(normals[i]);
+        }
+        self.vertex_map.insert(key, index);
+
+        index
+    }
+
+    fn build(self) -> TriangleMesh {
+        let mut mesh = TriangleMesh::new(self.positions, self.indices, Box::new(self.material));
+        if let Some(uvs) = self.uvs {
+            mesh = mesh.set_uvs(uvs);
+        }
+        if let Some(normals) = self.normals {
+            mesh = mesh.set_normals(normals);
+        }
+
+        mesh
+    }
+}
+
+// Loads a Wavefront OBJ file as one triangle mesh per material, ready to be passed to
+// `Bvh::new`. Polygons are split into triangle fans, and any material libraries are read
+// relative to the OBJ file. Faces without a material are given a plain grey diffuse material.
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<Vec<Box<dyn Hittable>>, LoadError> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+    parse_obj(path, &contents)
+}
+
+fn parse_obj(path: &Path, contents: &str) -> Result<Vec<Box<dyn Hittable>>, LoadError> {
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut positions: Vec<Point> = Vec::new();
+    let mut uvs: Vec<(f64, f64)> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+
+    let mut current_material_name = String::new();
+    let mut current_material: Arc<dyn Material> = Arc::new(Lambertian::new_from_color(Color::new(0.5, 0.5, 0.5)));
+
+    let mut meshes: Vec<MeshBuilder> = Vec::new();
+    let mut mesh_lookup: HashMap<(String, bool, bool), usize> = HashMap::new();
+
+    for (index, raw_line) in contents.lines().enumerate() {
+        let line = index + 1;
+        let text = raw_line.split('#').next().unwrap_or("").trim();
+        let mut tokens = text.split_whitespace();
+
+        let keyword = match tokens.next() {
+            Some(k) => k,
+            None => continue,
+        };
+        let values: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let v = parse_floats(path, line, &values, 3)?;
+                positions.push(Point::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let u = parse_floats(path, line, &values, 1)?[0];
+                let v = if values.len() > 1 { parse_floats(path, line, &values[1..], 1)?[0] } else { 0.0 };
+                uvs.push((u, v));
+            }
+            "vn" => {
+                let v = parse_floats(path, line, &values, 3)?;
+                normals.push(Vec3::new(v[0], v[1], v[2]));
+            }
+            "f" => {
+                if values.len() < 3 {
+                    return Err(LoadError::parse(path, line, format!("face needs at least 3 vertices, found {}", values.len())));
+                }
+
+                let counts = (positions.len(), uvs.len(), normals.len());
+                let corners = values
+                    .iter()
+                    .map(|v| parse_face_vertex(path, line, v, counts))
+                    .collect::<Result<Vec<FaceVertex>, LoadError>>()?;
+
+                let has_uvs = corners.iter().all(|c| c.uv.is_some());
+                let has_normals = corners.iter().all(|c| c.normal.is_some());
+                let key = (current_material_name.clone(), has_uvs, has_normals);
+                let mesh_index = *mesh_lookup.entry(key).or_insert_with(|| {
+                    meshes.push(MeshBuilder::new(Arc::clone(&current_material), has_uvs, has_normals));
+                    meshes.len() - 1
+                });
+
+                let mesh = &mut meshes[mesh_index];
+                let corner_indices: Vec<u32> = corners
+                    .iter()
+                    .map(|c| mesh.add_vertex(*c, &positions, &uvs, &normals))
+                    .collect();
+
+                for i in 1..corner_indices.len() - 1 {
+                    mesh.indices.push([corner_indices[0], corner_indices[i], corner_indices[i + 1]]);
+                }
+            }
+            "mtllib" => {
+                if values.is_empty() {
+                    return Err(LoadError::parse(path, line, "mtllib is missing a file name"));
+                }
+                materials.extend(load_mtl(directory.join(values.join(" ")))?);
+            }
+            "usemtl" => {
+                let name = values.join(" ");
+                current_material = match materials.get(&name) {
+                    Some(m) => Arc::clone(m),
+                    None => return Err(LoadError::parse(path, line, format!("unknown material '{}'", name))),
+                };
+                current_material_name = name;
+            }
+            // Groups, objects, smoothing groups, lines and points do not affect the triangles
+            _ => {}
+        }
+    }
+
+    Ok(meshes
+        .into_iter()
+        .map(|m| Box::new(m.build()) as Box<dyn Hittable>)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{HitRecord, Ray};
+
+    fn parse(contents: &str) -> Result<Vec<Box<dyn Hittable>>, LoadError> {
+        parse_obj(Path::new("test.obj"), contents)
+    }
+
+    // Fires a ray straight down the z axis at (x, y) and returns the nearest hit
+    fn hit_at(meshes: &[Box<dyn Hittable>], x: f64, y: f64) -> Option<HitRecord<'_>> {
+        let ray = Ray::new(Point::new(x, y, -1.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        meshes
+            .iter()
+            .filter_map(|m| m.hit(&ray, 0.001, f64::INFINITY))
+            .min_by(|a, b| a.t.total_cmp(&b.t))
+    }
+
+    fn assert_parse_error(result: Result<Vec<Box<dyn Hittable>>, LoadError>, expected_line: usize, text: &str) {
+        match result {
+            Err(LoadError::Parse { line, message, .. }) => {
+                assert_eq!(line, expected_line);
+                assert!(message.contains(text), "'{}' doesn't mention '{}'", message, text);
+            }
+            Err(e) => panic!("expected a parse error, got {}", e),
+            Ok(_) => panic!("expected a parse error"),
+        }
+    }
+
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\n";
+
+    #[test]
+    fn negative_indices_count_back_from_the_latest_vertex() {
+        let meshes = parse(&format!("{}f -4 -3 -2\n", SQUARE)).unwrap();
+        assert_eq!(meshes.len(), 1);
+        assert!(hit_at(&meshes, 0.25, 0.25).is_some());
+        assert!(hit_at(&meshes, 0.75, 0.75).is_none());
+    }
+
+    #[test]
+    fn indices_out_of_range_are_errors() {
+        assert_parse_error(parse(&format!("{}f -5 -3 -2\n", SQUARE)), 5, "out of range");
+        assert_parse_error(parse(&format!("{}f 1 2 5\n", SQUARE)), 5, "out of range");
+        assert_parse_error(parse(&format!("{}f 0 1 2\n", SQUARE)), 5, "cannot be zero");
+        assert_parse_error(parse(&format!("{}f 1 2\n", SQUARE)), 5, "at least 3 vertices");
+    }
+
+    #[test]
+    fn polygons_are_split_into_fans() {
+        let meshes = parse(&format!("{}f 1 2 4 3\n", SQUARE)).unwrap();
+        assert!(hit_at(&meshes, 0.25, 0.25).is_some());
+        assert!(hit_at(&meshes, 0.75, 0.75).is_some());
+    }
+
+    #[test]
+    fn faces_with_different_attributes_go_into_separate_meshes() {
+        let contents = format!(
+            "{}vt 0 0\nvt 1 0\nvt 0 1\nvt 1 1\nvn 0 0 -1\n\
+             f 1/1/1 2/2/1 3/3/1\nf 2//1 4//1 3//1\nf 1/1 2/2 3/3\nf 2 4 3\n",
+            SQUARE
+        );
+        let meshes = parse(&contents).unwrap();
+        assert_eq!(meshes.len(), 4);
+
+        // Texture coordinates are interpolated across the face, and match the positions here
+        let hit = hit_at(&meshes, 0.25, 0.5).unwrap();
+        assert!((hit.u - 0.25).abs() < 1e-9 && (hit.v - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unknown_materials_are_errors_with_their_line() {
+        assert_parse_error(parse(&format!("{}\nusemtl missing\nf 1 2 3\n", SQUARE)), 6, "unknown material 'missing'");
+    }
+}
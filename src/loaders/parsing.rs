@@ -0,0 +1,35 @@
+use std::path::Path;
+
+use super::LoadError;
+
+pub fn parse_floats(path: &Path, line: usize, values: &[&str], count: usize) -> Result<Vec<f64>, LoadError> {
+    if values.len() < count {
+        return Err(LoadError::parse(path, line, format!("expected {} numbers, found {}", count, values.len())));
+    }
+
+    values[..count]
+        .iter()
+        .map(|v| {
+            v.parse::<f64>()
+                .map_err(|_| LoadError::parse(path, line, format!("invalid number '{}'", v)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_leading_values() {
+        let values = parse_floats(Path::new("test"), 1, &["1", "-2.5", "3e2", "ignored"], 3).unwrap();
+        assert_eq!(values, vec![1.0, -2.5, 300.0]);
+    }
+
+    #[test]
+    fn reports_missing_and_invalid_values() {
+        let path = Path::new("test");
+        assert!(matches!(parse_floats(path, 7, &["1"], 2), Err(LoadError::Parse { line: 7, .. })));
+        assert!(matches!(parse_floats(path, 8, &["1", "x"], 2), Err(LoadError::Parse { line: 8, .. })));
+    }
+}
@@ -0,0 +1,489 @@
+use std::{convert::TryInto, fs, path::Path, str::Lines};
+
+use crate::{
+    graphics::{
+        materials::{Lambertian, Material},
+        models::TriangleMesh,
+        textures::VertexColorTexture,
+    },
+    math::{Color, Point, Vec3},
+};
+
+use super::LoadError;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<ScalarType> {
+        match name {
+            "char" | "int8" => Some(ScalarType::Int8),
+            "uchar" | "uint8" => Some(ScalarType::UInt8),
+            "short" | "int16" => Some(ScalarType::Int16),
+            "ushort" | "uint16" => Some(ScalarType::UInt16),
+            "int" | "int32" => Some(ScalarType::Int32),
+            "uint" | "uint32" => Some(ScalarType::UInt32),
+            "float" | "float32" => Some(ScalarType::Float32),
+            "double" | "float64" => Some(ScalarType::Float64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+}
+
+enum Property {
+    Scalar { name: String, kind: ScalarType },
+    List { name: String, count_kind: ScalarType, item_kind: ScalarType },
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+// Reads values from the body of the file, one element instance at a time
+struct BodyReader<'a> {
+    path: &'a Path,
+    format: Format,
+    data: &'a [u8],
+    offset: usize,
+    lines: Lines<'a>,
+    line: usize,
+    tokens: Vec<&'a str>,
+    token_index: usize,
+}
+
+impl<'a> BodyReader<'a> {
+    // ASCII files hold each element instance on its own line
+    fn begin_instance(&mut self) -> Result<(), LoadError> {
+        if self.format != Format::Ascii {
+            return Ok(());
+        }
+
+        loop {
+            self.line += 1;
+            match self.lines.next() {
+                Some(text) if text.trim().is_empty() => continue,
+                Some(text) => {
+                    self.tokens = text.split_whitespace().collect();
+                    self.token_index = 0;
+                    return Ok(());
+                }
+                None => return Err(LoadError::parse(self.path, self.line, "unexpected end of file")),
+            }
+        }
+    }
+
+    fn read(&mut self, kind: ScalarType) -> Result<f64, LoadError> {
+        if self.format == Format::Ascii {
+            let token = match self.tokens.get(self.token_index) {
+                Some(t) => *t,
+                None => return Err(LoadError::parse(self.path, self.line, "too few values for element")),
+            };
+            self.token_index += 1;
+
+            return token
+                .parse::<f64>()
+                .map_err(|_| LoadError::parse(self.path, self.line, format!("invalid number '{}'", token)));
+        }
+
+        let size = kind.size();
+        if self.offset + size > self.data.len() {
+            return Err(LoadError::binary(self.path, self.offset, "unexpected end of file"));
+        }
+
+        let mut bytes = [0u8; 8];
+        bytes[..size].copy_from_slice(&self.data[self.offset..self.offset + size]);
+        if self.format == Format::BinaryBigEndian {
+            bytes[..size].reverse();
+        }
+        self.offset += size;
+
+        let value = match kind {
+            ScalarType::Int8 => bytes[0] as i8 as f64,
+            ScalarType::UInt8 => bytes[0] as f64,
+            ScalarType::Int16 => i16::from_le_bytes(bytes[..2].try_into().unwrap()) as f64,
+            ScalarType::UInt16 => u16::from_le_bytes(bytes[..2].try_into().unwrap()) as f64,
+            ScalarType::Int32 => i32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            ScalarType::UInt32 => u32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            ScalarType::Float32 => f32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            ScalarType::Float64 => f64::from_le_bytes(bytes),
+        };
+
+        Ok(value)
+    }
+
+    fn error<M: Into<String>>(&self, message: M) -> LoadError {
+        match self.format {
+            Format::Ascii => LoadError::parse(self.path, self.line, message),
+            _ => LoadError::binary(self.path, self.offset, message),
+        }
+    }
+}
+
+fn parse_header(path: &Path, header: &str) -> Result<(Format, Vec<Element>), LoadError> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+
+    for (index, text) in header.lines().enumerate() {
+        let line = index + 1;
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+
+        match tokens.first().copied() {
+            Some("ply") if line == 1 => {}
+            Some("format") => {
+                format = match tokens.get(1).copied() {
+                    Some("ascii") => Some(Format::Ascii),
+                    Some("binary_little_endian") => Some(Format::BinaryLittleEndian),
+                    Some("binary_big_endian") => Some(Format::BinaryBigEndian),
+                    _ => return Err(LoadError::parse(path, line, format!("unknown format '{}'", text))),
+                };
+            }
+            Some("element") => {
+                if tokens.len() != 3 {
+                    return Err(LoadError::parse(path, line, "element needs a name and a count"));
+                }
+                let count = tokens[2]
+                    .parse()
+                    .map_err(|_| LoadError::parse(path, line, format!("invalid element count '{}'", tokens[2])))?;
+
+                elements.push(Element {
+                    name: tokens[1].to_string(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            Some("property") => {
+                let element = match elements.last_mut() {
+                    Some(e) => e,
+                    None => return Err(LoadError::parse(path, line, "property appears before any element")),
+                };
+                let scalar = |name: &str| {
+                    ScalarType::parse(name)
+                        .ok_or_else(|| LoadError::parse(path, line, format!("unknown property type '{}'", name)))
+                };
+
+                let property = match tokens.as_slice() {
+                    ["property", "list", count_kind, item_kind, name] => Property::List {
+                        name: name.to_string(),
+                        count_kind: scalar(count_kind)?,
+                        item_kind: scalar(item_kind)?,
+                    },
+                    ["property", kind, name] => Property::Scalar {
+                        name: name.to_string(),
+                        kind: scalar(kind)?,
+                    },
+                    _ => return Err(LoadError::parse(path, line, format!("malformed property '{}'", text))),
+                };
+                element.properties.push(property);
+            }
+            Some("comment") | Some("obj_info") | None => {}
+            _ => return Err(LoadError::parse(path, line, format!("unexpected header line '{}'", text))),
+        }
+    }
+
+    match format {
+        Some(f) => Ok((f, elements)),
+        None => Err(LoadError::parse(path, 1, "header has no format line")),
+    }
+}
+
+// The vertex data and triangles of a PLY file. Normals, texture coordinates and colours
+// are kept for every vertex or, when a file is missing some, left out entirely.
+struct PlyMesh {
+    positions: Vec<Point>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(f64, f64)>>,
+    colors: Option<Vec<Color>>,
+    indices: Vec<[u32; 3]>,
+}
+
+// Loads a PLY file, in ASCII or either binary layout, as a triangle mesh. Polygons are split
+// into triangle fans. Vertex normals and texture coordinates are used when present, and
+// vertex colours become the albedo of a diffuse material, which is otherwise plain grey.
+pub fn load_ply<P: AsRef<Path>>(path: P) -> Result<TriangleMesh, LoadError> {
+    let path = path.as_ref();
+    let data = fs::read(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+    let ply = parse_ply(path, &data)?;
+
+    let material: Box<dyn Material> = match ply.colors {
+        Some(colors) => Box::new(Lambertian::new(Box::new(VertexColorTexture::new(colors)))),
+        None => Box::new(Lambertian::new_from_color(Color::new(0.5, 0.5, 0.5))),
+    };
+
+    let mut mesh = TriangleMesh::new(ply.positions, ply.indices, material);
+    if let Some(normals) = ply.normals {
+        mesh = mesh.set_normals(normals);
+    }
+    if let Some(uvs) = ply.uvs {
+        mesh = mesh.set_uvs(uvs);
+    }
+
+    Ok(mesh)
+}
+
+fn per_vertex<T>(values: Vec<T>, vertex_count: usize) -> Option<Vec<T>> {
+    if values.len() == vertex_count { Some(values) } else { None }
+}
+
+fn parse_ply(path: &Path, data: &[u8]) -> Result<PlyMesh, LoadError> {
+    if !data.starts_with(b"ply") {
+        return Err(LoadError::parse(path, 1, "file does not start with 'ply'"));
+    }
+
+    let end_marker = b"end_header";
+    let header_end = match data.windows(end_marker.len()).position(|w| w == end_marker) {
+        Some(position) => position,
+        None => return Err(LoadError::parse(path, 1, "header has no end_header line")),
+    };
+    let body_start = match data[header_end..].iter().position(|&b| b == b'\n') {
+        Some(position) => header_end + position + 1,
+        None => data.len(),
+    };
+
+    let header = String::from_utf8_lossy(&data[..header_end]);
+    let header_lines = header.lines().count() + 1;
+    let (format, elements) = parse_header(path, &header)?;
+
+    let text = match format {
+        Format::Ascii => std::str::from_utf8(&data[body_start..])
+            .map_err(|_| LoadError::parse(path, header_lines, "ASCII body is not valid text"))?,
+        _ => "",
+    };
+
+    let mut reader = BodyReader {
+        path,
+        format,
+        data,
+        offset: body_start,
+        lines: text.lines(),
+        line: header_lines,
+        tokens: Vec::new(),
+        token_index: 0,
+    };
+
+    let mut positions: Vec<Point> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut uvs: Vec<(f64, f64)> = Vec::new();
+    let mut colors: Vec<Color> = Vec::new();
+    let mut indices: Vec<[u32; 3]> = Vec::new();
+
+    for element in elements.iter() {
+        for _ in 0..element.count {
+            reader.begin_instance()?;
+
+            // Scalar properties by name, with colours scaled into [0, 1]
+            let mut values: Vec<(&str, f64)> = Vec::new();
+            let mut face: Option<Vec<f64>> = None;
+
+            for property in element.properties.iter() {
+                match property {
+                    Property::Scalar { name, kind } => {
+                        let mut value = reader.read(*kind)?;
+                        let is_color = ["red", "green", "blue"].iter().any(|c| name.ends_with(c));
+                        if is_color {
+                            match kind {
+                                ScalarType::UInt8 => value /= 255.0,
+                                ScalarType::UInt16 => value /= 65535.0,
+                                _ => {}
+                            }
+                        }
+                        values.push((name, value));
+                    }
+                    Property::List { name, count_kind, item_kind } => {
+                        let count = reader.read(*count_kind)? as usize;
+                        let items = (0..count).map(|_| reader.read(*item_kind)).collect::<Result<Vec<f64>, LoadError>>()?;
+                        if name == "vertex_indices" || name == "vertex_index" {
+                            face = Some(items);
+                        }
+                    }
+                }
+            }
+
+            let get = |names: &[&str]| values.iter().find(|(n, _)| names.contains(n)).map(|(_, v)| *v);
+
+            match element.name.as_str() {
+                "vertex" => {
+                    match (get(&["x"]), get(&["y"]), get(&["z"])) {
+                        (Some(x), Some(y), Some(z)) => positions.push(Point::new(x, y, z)),
+                        _ => return Err(reader.error("vertex is missing an x, y or z coordinate")),
+                    }
+                    if let (Some(x), Some(y), Some(z)) = (get(&["nx"]), get(&["ny"]), get(&["nz"])) {
+                        normals.push(Vec3::new(x, y, z));
+                    }
+                    if let (Some(u), Some(v)) = (get(&["u", "s", "texture_u", "texture_s"]), get(&["v", "t", "texture_v", "texture_t"])) {
+                        uvs.push((u, v));
+                    }
+                    if let (Some(r), Some(g), Some(b)) = (
+                        get(&["red", "diffuse_red"]),
+                        get(&["green", "diffuse_green"]),
+                        get(&["blue", "diffuse_blue"]),
+                    ) {
+                        colors.push(Color::new(r, g, b));
+                    }
+                }
+                "face" => {
+                    let face = match face {
+                        Some(f) => f,
+                        None => return Err(reader.error("face has no vertex_indices list")),
+                    };
+                    if face.len() < 3 {
+                        return Err(reader.error(format!("face needs at least 3 vertices, found {}", face.len())));
+                    }
+                    if let Some(index) = face.iter().find(|&&i| i < 0.0 || i as usize >= positions.len()) {
+                        return Err(reader.error(format!("vertex index {} is out of range", index)));
+                    }
+
+                    for i in 1..face.len() - 1 {
+                        indices.push([face[0] as u32, face[i] as u32, face[i + 1] as u32]);
+                    }
+                }
+                // Other elements, such as edges, are read past and ignored
+                _ => {}
+            }
+        }
+    }
+
+    if indices.is_empty() {
+        return Err(LoadError::parse(path, header_lines, "file contains no faces"));
+    }
+
+    let vertex_count = positions.len();
+    Ok(PlyMesh {
+        normals: per_vertex(normals, vertex_count),
+        uvs: per_vertex(uvs, vertex_count),
+        colors: per_vertex(colors, vertex_count),
+        positions,
+        indices,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(data: &[u8]) -> Result<PlyMesh, LoadError> {
+        parse_ply(Path::new("test.ply"), data)
+    }
+
+    // A unit square with one colour per corner, as a single quad
+    const SQUARE_POSITIONS: [[f32; 3]; 4] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]];
+    const SQUARE_COLORS: [[u8; 3]; 4] = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [51, 102, 255]];
+
+    fn binary_square(format: &str, to_bytes: fn(f32) -> [u8; 4], index_bytes: fn(i32) -> [u8; 4]) -> Vec<u8> {
+        let mut data = format!(
+            "ply\nformat {} 1.0\ncomment a square\nelement vertex 4\nproperty float x\nproperty float y\n\
+             property float z\nproperty uchar red\nproperty uchar green\nproperty uchar blue\nelement face 1\n\
+             property list uchar int vertex_indices\nend_header\n",
+            format
+        )
+        .into_bytes();
+
+        for (position, color) in SQUARE_POSITIONS.iter().zip(SQUARE_COLORS.iter()) {
+            for &p in position.iter() {
+                data.extend_from_slice(&to_bytes(p));
+            }
+            data.extend_from_slice(color);
+        }
+        data.push(4);
+        for i in 0..4 {
+            data.extend_from_slice(&index_bytes(i));
+        }
+        data
+    }
+
+    fn assert_square(mesh: &PlyMesh) {
+        assert_eq!(mesh.positions.len(), 4);
+        for (p, expected) in mesh.positions.iter().zip(SQUARE_POSITIONS.iter()) {
+            assert!((*p - Point::new(expected[0] as f64, expected[1] as f64, expected[2] as f64)).near_zero());
+        }
+        assert_eq!(mesh.indices, vec![[0, 1, 2], [0, 2, 3]]);
+
+        let colors = mesh.colors.as_ref().expect("no vertex colours");
+        assert!((colors[0] - Color::new(1.0, 0.0, 0.0)).near_zero());
+        assert!((colors[3] - Color::new(0.2, 0.4, 1.0)).near_zero());
+        assert!(mesh.normals.is_none() && mesh.uvs.is_none());
+    }
+
+    #[test]
+    fn reads_ascii() {
+        let data = "ply\nformat ascii 1.0\nelement vertex 4\nproperty float x\nproperty float y\nproperty float z\n\
+                    property uchar red\nproperty uchar green\nproperty uchar blue\nelement face 1\n\
+                    property list uchar int vertex_indices\nend_header\n\
+                    0 0 0 255 0 0\n1 0 0 0 255 0\n\n1 1 0 0 0 255\n0 1 0 51 102 255\n4 0 1 2 3\n";
+        assert_square(&parse(data.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn reads_binary_little_endian() {
+        let data = binary_square("binary_little_endian", f32::to_le_bytes, i32::to_le_bytes);
+        assert_square(&parse(&data).unwrap());
+    }
+
+    #[test]
+    fn reads_binary_big_endian() {
+        let data = binary_square("binary_big_endian", f32::to_be_bytes, i32::to_be_bytes);
+        assert_square(&parse(&data).unwrap());
+    }
+
+    #[test]
+    fn skips_other_lists_and_elements() {
+        let data = "ply\nformat ascii 1.0\nelement vertex 3\nproperty double x\nproperty double y\nproperty double z\n\
+                    property float nx\nproperty float ny\nproperty float nz\nproperty float s\nproperty float t\n\
+                    element face 1\nproperty list uchar float texcoord\nproperty list uint uint vertex_index\n\
+                    element edge 1\nproperty int vertex1\nproperty int vertex2\nend_header\n\
+                    0 0 0 0 0 1 0 0\n1 0 0 0 0 1 1 0\n0 1 0 0 0 1 0 1\n6 0 0 1 0 0 1 3 0 1 2\n0 1\n";
+        let mesh = parse(data.as_bytes()).unwrap();
+        assert_eq!(mesh.indices, vec![[0, 1, 2]]);
+        assert_eq!(mesh.uvs.unwrap()[2], (0.0, 1.0));
+        assert!((mesh.normals.unwrap()[1] - Vec3::new(0.0, 0.0, 1.0)).near_zero());
+        assert!(mesh.colors.is_none());
+    }
+
+    #[test]
+    fn scales_colours_by_their_type() {
+        let data = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+                    property ushort red\nproperty ushort green\nproperty float blue\nelement face 1\n\
+                    property list uchar int vertex_indices\nend_header\n\
+                    0 0 0 65535 0 0.5\n1 0 0 0 65535 0.5\n0 1 0 0 0 0.5\n3 0 1 2\n";
+        let colors = parse(data.as_bytes()).unwrap().colors.unwrap();
+        assert!((colors[0] - Color::new(1.0, 0.0, 0.5)).near_zero());
+    }
+
+    #[test]
+    fn reports_bad_indices_and_truncated_bodies() {
+        let data = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+                    element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+                    0 0 0\n1 0 0\n0 1 0\n3 0 1 3\n";
+        assert!(matches!(parse(data.as_bytes()), Err(LoadError::Parse { line: 13, .. })));
+
+        let mut data = binary_square("binary_little_endian", f32::to_le_bytes, i32::to_le_bytes);
+        data.truncate(data.len() - 2);
+        assert!(matches!(parse(&data), Err(LoadError::Binary { .. })));
+    }
+}
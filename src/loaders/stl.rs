@@ -0,0 +1,230 @@
+use std::{collections::HashMap, convert::TryInto, fs, path::Path};
+
+use crate::{
+    graphics::{materials::Lambertian, models::TriangleMesh},
+    math::{Color, Point},
+};
+
+use super::{LoadError, parsing::parse_floats};
+
+const HEADER_SIZE: usize = 80;
+const FACET_SIZE: usize = 50;
+
+// STL stores every facet separately, so identical corners are merged into shared vertices
+struct VertexWelder {
+    lookup: HashMap<[u64; 3], u32>,
+    positions: Vec<Point>,
+}
+
+impl VertexWelder {
+    fn new() -> Self {
+        VertexWelder {
+            lookup: HashMap::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, p: Point) -> u32 {
+        // Adding zero turns -0.0 into 0.0, which has different bits but is the same place
+        let key = [(p.x + 0.0).to_bits(), (p.y + 0.0).to_bits(), (p.z + 0.0).to_bits()];
+        let positions = &mut self.positions;
+
+        *self.lookup.entry(key).or_insert_with(|| {
+            positions.push(p);
+            (positions.len() - 1) as u32
+        })
+    }
+}
+
+fn is_binary(data: &[u8]) -> bool {
+    // ASCII files start with "solid", but so do some binary ones, so trust the size first
+    if data.len() >= HEADER_SIZE + 4 {
+        let count = u32::from_le_bytes(data[HEADER_SIZE..HEADER_SIZE + 4].try_into().unwrap()) as usize;
+        if data.len() == HEADER_SIZE + 4 + count * FACET_SIZE {
+            return true;
+        }
+    }
+
+    !data.starts_with(b"solid")
+}
+
+fn read_binary(path: &Path, data: &[u8], welder: &mut VertexWelder) -> Result<Vec<[u32; 3]>, LoadError> {
+    if data.len() < HEADER_SIZE + 4 {
+        return Err(LoadError::binary(path, data.len(), "file is too short for a binary STL header"));
+    }
+
+    let count = u32::from_le_bytes(data[HEADER_SIZE..HEADER_SIZE + 4].try_into().unwrap()) as usize;
+    let mut indices = Vec::with_capacity(count.min(data.len() / FACET_SIZE));
+
+    for facet in 0..count {
+        // Each facet is a normal, three corners and an attribute byte count
+        let start = HEADER_SIZE + 4 + facet * FACET_SIZE;
+        if start + FACET_SIZE > data.len() {
+            return Err(LoadError::binary(
+                path,
+                data.len(),
+                format!("file ends after {} of {} facets", facet, count),
+            ));
+        }
+
+        let read = |i: usize| {
+            let offset = start + 12 + i * 4;
+            f32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as f64
+        };
+
+        let mut triangle = [0; 3];
+        for (corner, index) in triangle.iter_mut().enumerate() {
+            *index = welder.add(Point::new(read(corner * 3), read(corner * 3 + 1), read(corner * 3 + 2)));
+        }
+        indices.push(triangle);
+    }
+
+    Ok(indices)
+}
+
+fn read_ascii(path: &Path, data: &[u8], welder: &mut VertexWelder) -> Result<Vec<[u32; 3]>, LoadError> {
+    let text = std::str::from_utf8(data).map_err(|_| LoadError::parse(path, 1, "file is not valid text"))?;
+
+    let mut indices = Vec::new();
+    let mut corners: Vec<Point> = Vec::new();
+
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = index + 1;
+        let tokens: Vec<&str> = raw_line.split_whitespace().collect();
+
+        match tokens.first().copied() {
+            Some("vertex") => {
+                let v = parse_floats(path, line, &tokens[1..], 3)?;
+                corners.push(Point::new(v[0], v[1], v[2]));
+            }
+            Some("facet") => corners.clear(),
+            Some("endfacet") => {
+                if corners.len() < 3 {
+                    return Err(LoadError::parse(path, line, format!("facet has {} vertices, expected 3", corners.len())));
+                }
+
+                // Facets should be triangles, but any extra corners are fanned out
+                let first = welder.add(corners[0]);
+                for i in 1..corners.len() - 1 {
+                    indices.push([first, welder.add(corners[i]), welder.add(corners[i + 1])]);
+                }
+                corners.clear();
+            }
+            Some("solid") | Some("endsolid") | Some("outer") | Some("endloop") | None => {}
+            Some(keyword) => {
+                return Err(LoadError::parse(path, line, format!("unexpected keyword '{}'", keyword)));
+            }
+        }
+    }
+
+    Ok(indices)
+}
+
+// Loads a binary or ASCII STL file as a triangle mesh with a plain grey diffuse material.
+// Facet normals are ignored in favour of the winding of each triangle.
+pub fn load_stl<P: AsRef<Path>>(path: P) -> Result<TriangleMesh, LoadError> {
+    let path = path.as_ref();
+    let data = fs::read(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+    let (positions, indices) = parse_stl(path, &data)?;
+
+    Ok(TriangleMesh::new(
+        positions,
+        indices,
+        Box::new(Lambertian::new_from_color(Color::new(0.5, 0.5, 0.5))),
+    ))
+}
+
+fn parse_stl(path: &Path, data: &[u8]) -> Result<(Vec<Point>, Vec<[u32; 3]>), LoadError> {
+    let mut welder = VertexWelder::new();
+    let indices = if is_binary(data) {
+        read_binary(path, data, &mut welder)?
+    } else {
+        read_ascii(path, data, &mut welder)?
+    };
+
+    if indices.is_empty() {
+        return Err(LoadError::parse(path, 1, "file contains no facets"));
+    }
+
+    Ok((welder.positions, indices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(data: &[u8]) -> Result<(Vec<Point>, Vec<[u32; 3]>), LoadError> {
+        parse_stl(Path::new("test.stl"), data)
+    }
+
+    // Two facets sharing an edge, making a unit square
+    const FACETS: [[[f32; 3]; 3]; 2] = [
+        [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+        [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+    ];
+
+    fn binary(header: &[u8], facets: &[[[f32; 3]; 3]], count: u32) -> Vec<u8> {
+        let mut data = header.to_vec();
+        data.resize(HEADER_SIZE, b' ');
+        data.extend_from_slice(&count.to_le_bytes());
+        for facet in facets.iter() {
+            data.extend_from_slice(&[0; 12]);
+            for v in facet.iter().flatten() {
+                data.extend_from_slice(&v.to_le_bytes());
+            }
+            data.extend_from_slice(&[0; 2]);
+        }
+        data
+    }
+
+    fn ascii(facets: &[[[f32; 3]; 3]]) -> String {
+        let mut text = String::from("solid square\n");
+        for facet in facets.iter() {
+            text.push_str("  facet normal 0 0 1\n    outer loop\n");
+            for v in facet.iter() {
+                text.push_str(&format!("      vertex {:?} {:?} {:?}\n", v[0], v[1], v[2]));
+            }
+            text.push_str("    endloop\n  endfacet\n");
+        }
+        text.push_str("endsolid square\n");
+        text
+    }
+
+    #[test]
+    fn reads_ascii_and_welds_shared_corners() {
+        let (positions, indices) = parse(ascii(&FACETS).as_bytes()).unwrap();
+        assert_eq!(positions.len(), 4);
+        assert_eq!(indices, vec![[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn reads_binary_whose_header_starts_with_solid() {
+        let data = binary(b"solid exported by a CAD package", &FACETS, 2);
+        assert!(is_binary(&data));
+
+        let (positions, indices) = parse(&data).unwrap();
+        assert_eq!(positions.len(), 4);
+        assert_eq!(indices, vec![[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn welds_negative_zero() {
+        let mut facets = FACETS;
+        facets[1][0] = [-0.0, -0.0, 0.0];
+        let (positions, _) = parse(ascii(&facets).as_bytes()).unwrap();
+        assert_eq!(positions.len(), 4);
+    }
+
+    #[test]
+    fn reports_truncated_facets() {
+        let data = binary(b"binary", &FACETS[..1], 2);
+        assert!(matches!(parse(&data), Err(LoadError::Binary { .. })));
+
+        let mut data = binary(b"binary", &FACETS, 2);
+        data.truncate(data.len() - 1);
+        assert!(matches!(parse(&data), Err(LoadError::Binary { .. })));
+
+        let text = "solid bad\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nendloop\nendfacet\nendsolid\n";
+        assert!(matches!(parse(text.as_bytes()), Err(LoadError::Parse { line: 7, .. })));
+    }
+}
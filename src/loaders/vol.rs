@@ -0,0 +1,127 @@
+use std::{convert::TryInto, fs, path::Path};
+
+use crate::{
+    graphics::{Aabb, volumes::VoxelGrid},
+    math::Point,
+};
+
+use super::LoadError;
+
+const HEADER_SIZE: usize = 48;
+const ENCODING_FLOAT32: i32 = 1;
+const ENCODING_UINT8: i32 = 3;
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_f32(data: &[u8], offset: usize) -> f64 {
+    f32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as f64
+}
+
+// Loads a Mitsuba style binary grid volume as a density field. Float32 and uint8 encodings
+// are supported, and only the first channel of multi channel grids is used.
+pub fn load_vol<P: AsRef<Path>>(path: P) -> Result<VoxelGrid, LoadError> {
+    let path = path.as_ref();
+    let data = fs::read(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+    parse_vol(path, &data)
+}
+
+fn parse_vol(path: &Path, data: &[u8]) -> Result<VoxelGrid, LoadError> {
+    if data.len() < HEADER_SIZE {
+        return Err(LoadError::binary(path, data.len(), "file is too short for a volume header"));
+    }
+    if &data[..3] != b"VOL" || data[3] != 3 {
+        return Err(LoadError::binary(path, 0, "not a version 3 VOL file"));
+    }
+
+    let encoding = read_i32(data, 4);
+    let value_size = match encoding {
+        ENCODING_FLOAT32 => 4,
+        ENCODING_UINT8 => 1,
+        _ => return Err(LoadError::binary(path, 4, format!("unsupported encoding {}", encoding))),
+    };
+
+    let dimensions = [read_i32(data, 8), read_i32(data, 12), read_i32(data, 16), read_i32(data, 20)];
+    if let Some(position) = dimensions.iter().position(|&d| d <= 0) {
+        return Err(LoadError::binary(path, 8 + position * 4, "grid dimensions must be positive"));
+    }
+    let [width, height, depth, channels] = dimensions.map(|d| d as usize);
+
+    let bounds = Aabb::new(
+        Point::new(read_f32(data, 24), read_f32(data, 28), read_f32(data, 32)),
+        Point::new(read_f32(data, 36), read_f32(data, 40), read_f32(data, 44)),
+    );
+    let extent = bounds.maximum - bounds.minimum;
+    if !(extent.x > 0.0 && extent.y > 0.0 && extent.z > 0.0) {
+        return Err(LoadError::binary(path, 24, "grid bounds must have a positive size"));
+    }
+
+    let too_large = || LoadError::binary(path, 8, format!("a {}x{}x{} grid is too large", width, height, depth));
+    let count = width.checked_mul(height).and_then(|c| c.checked_mul(depth)).ok_or_else(too_large)?;
+    let expected = count
+        .checked_mul(channels * value_size)
+        .and_then(|size| size.checked_add(HEADER_SIZE))
+        .ok_or_else(too_large)?;
+    if data.len() < expected {
+        return Err(LoadError::binary(
+            path,
+            data.len(),
+            format!("file ends before the {}x{}x{} grid is complete", width, height, depth),
+        ));
+    }
+
+    let values = (0..count)
+        .map(|i| {
+            let offset = HEADER_SIZE + i * channels * value_size;
+            match encoding {
+                ENCODING_FLOAT32 => read_f32(data, offset).max(0.0),
+                _ => data[offset] as f64 / 255.0,
+            }
+        })
+        .collect();
+
+    Ok(VoxelGrid::new((width, height, depth), values, bounds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(dimensions: [i32; 4], bounds: [f32; 6]) -> Vec<u8> {
+        let mut data = b"VOL".to_vec();
+        data.push(3);
+        data.extend_from_slice(&ENCODING_UINT8.to_le_bytes());
+        for d in dimensions.iter() {
+            data.extend_from_slice(&d.to_le_bytes());
+        }
+        for b in bounds.iter() {
+            data.extend_from_slice(&b.to_le_bytes());
+        }
+        data
+    }
+
+    const UNIT: [f32; 6] = [0.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+
+    #[test]
+    fn reads_a_small_grid() {
+        let mut data = header([2, 1, 1, 1], UNIT);
+        data.extend_from_slice(&[0, 255]);
+
+        let grid = parse_vol(Path::new("test.vol"), &data).unwrap();
+        assert_eq!(grid.resolution(), (2, 1, 1));
+    }
+
+    #[test]
+    fn rejects_dimensions_that_overflow() {
+        let data = header([i32::MAX, i32::MAX, i32::MAX, i32::MAX], UNIT);
+        assert!(matches!(parse_vol(Path::new("test.vol"), &data), Err(LoadError::Binary { offset: 8, .. })));
+    }
+
+    #[test]
+    fn rejects_empty_bounds() {
+        let mut data = header([1, 1, 1, 1], [0.0, 0.0, 0.0, 1.0, 0.0, 1.0]);
+        data.push(255);
+        assert!(matches!(parse_vol(Path::new("test.vol"), &data), Err(LoadError::Binary { offset: 24, .. })));
+    }
+}
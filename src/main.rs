@@ -1,5 +1,7 @@
+use std::{env, sync::Arc};
+
 use rand::Rng;
-use ray_tracer::{graphics::{Bvh, Camera, Hittable, materials::{Dielectric, Lambertian, Metal}, models::{MovingSphere, Sphere}, textures::{CheckerTexture, ImageTexture, PerlinTexture}}, math::{Color, Point, Vec3}, render_image, utils::Config};
+use ray_tracer::{graphics::{backgrounds::{Background, GradientBackground, SolidBackground}, Aabb, Bvh, Camera, Hittable, Instance, Transform, materials::{Conductor, Dielectric, DiffuseLight, Dispersion, Lambertian, Material, RoughDielectric}, models::{Cuboid, MovingSphere, Sphere, Triangle, XyRect, XzRect, YzRect}, textures::{CheckerTexture, ImageTexture, PerlinTexture, SolidColor}, volumes::{Atmosphere, ConstantMedium, HeterogeneousMedium, NoiseDensity}}, integrators::{AmbientOcclusion, BidirectionalPathTracer, DebugIntegrator, DebugView, Integrator, PathTracer, PhotonMapper, SpectralPathTracer, Whitted}, math::{Color, Matrix4, Point, Vec3}, output::{OutputFormat, save_image, save_image_with_format}, render_image, utils::Config};
 
 fn random_world() -> Vec<Box<dyn Hittable>> {
     let checker = Box::new(CheckerTexture::new_from_colors(Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9)));
@@ -39,7 +41,7 @@ fn random_world() -> Vec<Box<dyn Hittable>> {
                 } else if choose_mat < 0.95 {
                     let albedo = Color::random(0.5, 1);
                     let roughness = rng.gen_range(0.0..0.5);
-                    let sphere_material = Metal::new(albedo, roughness);
+                    let sphere_material = Conductor::new_from_color(albedo, roughness);
                     world.push(Box::new(Sphere::new(
                         center,
                         0.2,
@@ -71,7 +73,7 @@ fn random_world() -> Vec<Box<dyn Hittable>> {
         Box::new(material),
     )));
 
-    let material = Metal::new(Color::new(0.7, 0.6, 0.5), 0.0);
+    let material = Conductor::new_from_color(Color::new(0.7, 0.6, 0.5), 0.0);
     world.push(Box::new(Sphere::new(
         Point::new(4, 1, 0),
         1.0,
@@ -134,15 +136,280 @@ fn earth() -> Vec<Box<dyn Hittable>> {
     ]
 }
 
-fn setup_config() -> Config{
+fn simple_light(lights: &mut Vec<Box<dyn Hittable>>) -> Vec<Box<dyn Hittable>> {
+    let light: Arc<dyn Hittable> = Arc::new(Sphere::new(
+        Point::new(0, 7, 0),
+        2.0,
+        Box::new(DiffuseLight::new_from_color(Color::new(4, 4, 4))),
+    ));
+    lights.push(Box::new(light.clone()));
+
+    vec![
+        Box::new(
+            Sphere::new(
+                Point::new(0, -1000, 0),
+                1000.0,
+                Box::new(Lambertian::new(Box::new(PerlinTexture::new_with_scale(4.0))))
+            )
+        ),
+        Box::new(
+            Sphere::new(
+                Point::new(0, 2, 0),
+                2.0,
+                Box::new(Lambertian::new(Box::new(PerlinTexture::new_with_scale(4.0))))
+            )
+        ),
+        Box::new(light),
+    ]
+}
+
+fn cornell_walls() -> Vec<Box<dyn Hittable>> {
+    let red = || Box::new(Lambertian::new_from_color(Color::new(0.65, 0.05, 0.05)));
+    let white = || Box::new(Lambertian::new_from_color(Color::new(0.73, 0.73, 0.73)));
+    let green = || Box::new(Lambertian::new_from_color(Color::new(0.12, 0.45, 0.15)));
+
+    vec![
+        Box::new(YzRect::new(0.0, 555.0, 0.0, 555.0, 555.0, green())),
+        Box::new(YzRect::new(0.0, 555.0, 0.0, 555.0, 0.0, red())),
+        Box::new(XzRect::new(0.0, 555.0, 0.0, 555.0, 0.0, white())),
+        Box::new(XzRect::new(0.0, 555.0, 0.0, 555.0, 555.0, white())),
+        Box::new(XyRect::new(0.0, 555.0, 0.0, 555.0, 555.0, white())),
+    ]
+}
+
+fn cornell_boxes() -> [Box<dyn Hittable>; 2] {
+    let white = || Box::new(Lambertian::new_from_color(Color::new(0.73, 0.73, 0.73)));
+
+    [
+        Box::new(Transform::new(
+            Box::new(Cuboid::new(Point::ZERO, Point::new(165, 330, 165), white())),
+            Matrix4::translation(Vec3::new(265, 0, 295)) * Matrix4::rotation_y(15),
+        )),
+        Box::new(Transform::new(
+            Box::new(Cuboid::new(Point::ZERO, Point::new(165, 165, 165), white())),
+            Matrix4::translation(Vec3::new(130, 0, 65)) * Matrix4::rotation_y(-18),
+        )),
+    ]
+}
+
+fn cornell_box(lights: &mut Vec<Box<dyn Hittable>>) -> Vec<Box<dyn Hittable>> {
+    let light: Arc<dyn Hittable> = Arc::new(XzRect::new(
+        213.0, 343.0, 227.0, 332.0, 554.0,
+        Box::new(DiffuseLight::new_from_color(Color::new(15, 15, 15))),
+    ));
+    lights.push(Box::new(light.clone()));
+
+    let mut world = cornell_walls();
+    world.push(Box::new(light));
+    world.extend(cornell_boxes());
+    world
+}
+
+fn cornell_smoke(lights: &mut Vec<Box<dyn Hittable>>) -> Vec<Box<dyn Hittable>> {
+    let light: Arc<dyn Hittable> = Arc::new(XzRect::new(
+        113.0, 443.0, 127.0, 432.0, 554.0,
+        Box::new(DiffuseLight::new_from_color(Color::new(7, 7, 7))),
+    ));
+    lights.push(Box::new(light.clone()));
+
+    let mut world = cornell_walls();
+    world.push(Box::new(light));
+
+    let [tall, short] = cornell_boxes();
+    world.push(Box::new(ConstantMedium::new_from_color(tall, 0.01, Color::ZERO)));
+    world.push(Box::new(ConstantMedium::new_from_color(short, 0.01, Color::ONE)));
+    world
+}
+
+// The Cornell box lit only through a small hole in the base of a closed light fixture, so
+// hardly any paths from the camera can find the light
+fn cornell_fixture(lights: &mut Vec<Box<dyn Hittable>>) -> Vec<Box<dyn Hittable>> {
+    let light: Arc<dyn Hittable> = Arc::new(XzRect::new(
+        213.0, 343.0, 227.0, 332.0, 554.0,
+        Box::new(DiffuseLight::new_from_color(Color::new(250, 250, 250))),
+    ));
+    lights.push(Box::new(light.clone()));
+
+    let shade = || Box::new(Lambertian::new_from_color(Color::new(0.73, 0.73, 0.73)));
+
+    let mut world = cornell_walls();
+    world.push(Box::new(light));
+    world.extend(cornell_boxes());
+
+    world.push(Box::new(XyRect::new(203.0, 353.0, 504.0, 555.0, 217.0, shade())));
+    world.push(Box::new(XyRect::new(203.0, 353.0, 504.0, 555.0, 342.0, shade())));
+    world.push(Box::new(YzRect::new(504.0, 555.0, 217.0, 342.0, 203.0, shade())));
+    world.push(Box::new(YzRect::new(504.0, 555.0, 217.0, 342.0, 353.0, shade())));
+
+    // The base is made of four pieces around the hole
+    world.push(Box::new(XzRect::new(203.0, 268.0, 217.0, 342.0, 504.0, shade())));
+    world.push(Box::new(XzRect::new(288.0, 353.0, 217.0, 342.0, 504.0, shade())));
+    world.push(Box::new(XzRect::new(268.0, 288.0, 217.0, 270.0, 504.0, shade())));
+    world.push(Box::new(XzRect::new(268.0, 288.0, 290.0, 342.0, 504.0, shade())));
+    world
+}
+
+// Clear and frosted glass on a table under a small light, for checking caustics
+fn glass_table(lights: &mut Vec<Box<dyn Hittable>>) -> Vec<Box<dyn Hittable>> {
+    let light: Arc<dyn Hittable> = Arc::new(Sphere::new(
+        Point::new(2, 3, 1.5),
+        0.2,
+        Box::new(DiffuseLight::new_from_color(Color::new(80, 80, 80))),
+    ));
+    lights.push(Box::new(light.clone()));
+
+    vec![
+        Box::new(XzRect::new(-5.0, 5.0, -5.0, 5.0, 0.0, Box::new(Lambertian::new_from_color(Color::new(0.8, 0.75, 0.7))))),
+        Box::new(XyRect::new(-5.0, 5.0, 0.0, 5.0, -2.0, Box::new(Lambertian::new_from_color(Color::new(0.73, 0.73, 0.73))))),
+        Box::new(Sphere::new(Point::new(-0.4, 0.5, 0), 0.5, Box::new(Dielectric::new(1.5)))),
+        Box::new(Cuboid::new(Point::new(0.35, 0, -0.6), Point::new(0.95, 0.6, 0), Box::new(Dielectric::new(1.5)))),
+        Box::new(Sphere::new(
+            Point::new(1.3, 0.3, 0.5),
+            0.3,
+            Box::new(RoughDielectric::new_from_roughness(1.5, 0.3).set_tint(Color::new(0.8, 0.9, 1.0))),
+        )),
+        Box::new(light),
+    ]
+}
+
+// A triangular prism of flint glass under a strip light, with a diamond beside it, for
+// checking dispersion. The prism is turned so the strip shows through two of its faces.
+fn prism(lights: &mut Vec<Box<dyn Hittable>>) -> Vec<Box<dyn Hittable>> {
+    let strip: Arc<dyn Hittable> = Arc::new(XzRect::new(
+        -4.0, 4.0, -0.75, -0.65, 3.0,
+        Box::new(DiffuseLight::new_from_color(Color::new(30, 30, 30))),
+    ));
+    let lamp: Arc<dyn Hittable> = Arc::new(Sphere::new(
+        Point::new(1.5, 3, 1),
+        0.2,
+        Box::new(DiffuseLight::new_from_color(Color::new(40, 40, 40))),
+    ));
+    lights.push(Box::new(strip.clone()));
+    lights.push(Box::new(lamp.clone()));
+
+    let mut world: Vec<Box<dyn Hittable>> = vec![
+        Box::new(XzRect::new(-10.0, 10.0, -10.0, 10.0, 0.0, Box::new(Lambertian::new_from_color(Color::new(0.5, 0.5, 0.5))))),
+        Box::new(Sphere::new(Point::new(1.6, 0.35, 0.8), 0.35, Box::new(Dielectric::new_dispersive(Dispersion::diamond())))),
+        Box::new(strip),
+        Box::new(lamp),
+    ];
+
+    let center = Point::new(-0.2, 0.6, 0);
+    let corner = |x: f64, i: u32| {
+        let angle = (5.0 + 120.0 * i as f64).to_radians();
+        Point::new(x, center.y + 0.5 * angle.sin(), 0.5 * angle.cos())
+    };
+
+    // The glass needs every face to be wound with its normal pointing out of the prism
+    let mut face = |a: Point, b: Point, c: Point| {
+        let glass = Box::new(Dielectric::new_dispersive(Dispersion::sf11()));
+        if (b - a).cross(c - a).dot(a - center) < 0.0 {
+            world.push(Box::new(Triangle::new(a, c, b, glass)));
+        } else {
+            world.push(Box::new(Triangle::new(a, b, c, glass)));
+        }
+    };
+
+    face(corner(-1.2, 0), corner(-1.2, 1), corner(-1.2, 2));
+    face(corner(0.8, 0), corner(0.8, 1), corner(0.8, 2));
+    for i in 0..3 {
+        let j = (i + 1) % 3;
+        face(corner(-1.2, i), corner(-1.2, j), corner(0.8, j));
+        face(corner(-1.2, i), corner(0.8, j), corner(0.8, i));
+    }
+    world
+}
+
+fn forest() -> Vec<Box<dyn Hittable>> {
+    let trunk = Box::new(Lambertian::new_from_color(Color::new(0.35, 0.2, 0.1)));
+    let leaves = Box::new(Lambertian::new_from_color(Color::new(0.1, 0.4, 0.1)));
+
+    // Every tree shares this one prototype
+    let tree: Arc<dyn Hittable> = Arc::new(Bvh::new(
+        vec![
+            Box::new(Cuboid::new(Point::new(-0.1, 0, -0.1), Point::new(0.1, 1, 0.1), trunk)),
+            Box::new(Sphere::new(Point::new(0, 1.3, 0), 0.5, leaves)),
+        ],
+        0.0,
+        1.0,
+    ));
+    let autumn: Arc<dyn Material> = Arc::new(Lambertian::new_from_color(Color::new(0.8, 0.35, 0.05)));
+
+    let mut world: Vec<Box<dyn Hittable>> = vec![Box::new(Sphere::new(
+        Point::new(0, -1000, 0),
+        1000.0,
+        Box::new(Lambertian::new_from_color(Color::new(0.4, 0.5, 0.2))),
+    ))];
+
+    let mut rng = rand::thread_rng();
+
+    for a in -50..50 {
+        for b in -50..50 {
+            let position = Vec3::new(
+                a as f64 + 0.8 * rng.gen::<f64>(),
+                0,
+                b as f64 + 0.8 * rng.gen::<f64>(),
+            );
+            let matrix = Matrix4::translation(position)
+                * Matrix4::rotation_y(rng.gen_range(0.0..360.0))
+                * Matrix4::scaling(Vec3::ONE * rng.gen_range(0.5..1.0));
+
+            let mut instance = Instance::new(tree.clone(), matrix);
+            if rng.gen::<f64>() < 0.2 {
+                instance = instance.set_material(autumn.clone());
+            }
+            world.push(Box::new(instance));
+        }
+    }
+
+    world
+}
+
+fn clouds() -> Vec<Box<dyn Hittable>> {
+    let ground = Box::new(Lambertian::new_from_color(Color::new(0.5, 0.5, 0.5)));
+    let cloud = HeterogeneousMedium::new(
+        Box::new(Sphere::new(Point::new(-1.2, 1.5, 0), 1.5, boundary_material())),
+        Box::new(NoiseDensity::new(2.5)),
+        25.0,
+        Box::new(SolidColor::new(Color::new(0.95, 0.95, 0.95))),
+    )
+    .set_anisotropy(0.6);
+    let fireball = HeterogeneousMedium::new(
+        Box::new(Sphere::new(Point::new(2.0, 1.0, 0), 1.0, boundary_material())),
+        Box::new(NoiseDensity::new(4.0)),
+        15.0,
+        Box::new(SolidColor::new(Color::new(0.3, 0.3, 0.3))),
+    )
+    .set_emission(Box::new(SolidColor::new(Color::new(2.0, 0.6, 0.1))));
+
+    vec![
+        Box::new(Sphere::new(Point::new(0, -1000, 0), 1000.0, ground)),
+        Box::new(cloud),
+        Box::new(fireball),
+    ]
+}
+
+// Volume boundaries are never shaded, so any material will do
+fn boundary_material() -> Box<Lambertian> {
+    Box::new(Lambertian::new_from_color(Color::ZERO))
+}
+
+fn setup_config(integrator: &str) -> Config{
 
     let scene_selector = 2;
 
-    let aspect_ratio = 16.0 / 9.0;
+    let mut aspect_ratio = 16.0 / 9.0;
     let image_width = 1200;
 
-    let fov = 20.0;
+    let mut look_from = Point::new(13, 2, 3);
+    let mut look_at = Point::ZERO;
+    let mut fov = 20.0;
     let mut aperture = 0.0;
+    let mut background: Box<dyn Background> = Box::new(GradientBackground::sky());
+    let mut atmosphere = None;
+    let mut lights: Vec<Box<dyn Hittable>> = Vec::new();
+    let mut photon_radius = 5.0;
+    let mut photon_focus = None;
 
     let world = match scene_selector {
         0 => {
@@ -157,15 +424,90 @@ fn setup_config() -> Config{
             earth()
         }
 
+        3 => {
+            background = Box::new(SolidBackground::new(Color::ZERO));
+            simple_light(&mut lights)
+        }
+
+        4 => {
+            aspect_ratio = 1.0;
+            look_from = Point::new(278, 278, -800);
+            look_at = Point::new(278, 278, 0);
+            fov = 40.0;
+            background = Box::new(SolidBackground::new(Color::ZERO));
+            cornell_box(&mut lights)
+        }
+
+        5 => {
+            look_from = Point::new(0, 6, 20);
+            look_at = Point::new(0, 0, 0);
+            fov = 40.0;
+            forest()
+        }
+
+        6 => {
+            aspect_ratio = 1.0;
+            look_from = Point::new(278, 278, -800);
+            look_at = Point::new(278, 278, 0);
+            fov = 40.0;
+            background = Box::new(SolidBackground::new(Color::ZERO));
+            cornell_smoke(&mut lights)
+        }
+
+        7 => {
+            look_from = Point::new(0, 2, 12);
+            look_at = Point::new(0, 1.2, 0);
+            fov = 35.0;
+            clouds()
+        }
+
+        8 => {
+            aspect_ratio = 1.0;
+            look_from = Point::new(278, 278, -800);
+            look_at = Point::new(278, 278, 0);
+            fov = 40.0;
+            background = Box::new(SolidBackground::new(Color::ZERO));
+            atmosphere = Some(Atmosphere::new(Color::ONE * 0.0001, Color::ONE * 0.0006).set_anisotropy(0.3));
+            cornell_box(&mut lights)
+        }
+
+        9 => {
+            aspect_ratio = 1.0;
+            look_from = Point::new(278, 278, -800);
+            look_at = Point::new(278, 278, 0);
+            fov = 40.0;
+            background = Box::new(SolidBackground::new(Color::ZERO));
+            cornell_fixture(&mut lights)
+        }
+
+        10 => {
+            look_from = Point::new(0, 1.2, 4);
+            look_at = Point::new(0, 0.4, 0);
+            fov = 30.0;
+            background = Box::new(SolidBackground::new(Color::ZERO));
+            photon_radius = 0.02;
+            glass_table(&mut lights)
+        }
+
+        11 => {
+            look_from = Point::new(0, 0.6, 5);
+            look_at = Point::new(0, 0.45, 0);
+            fov = 30.0;
+            background = Box::new(SolidBackground::new(Color::ZERO));
+            prism(&mut lights)
+        }
+
         _ => {
             aperture = 0.1;
+            photon_radius = 0.05;
+            photon_focus = Some(Aabb::new(Point::new(-12, -1, -12), Point::new(12, 3, 12)));
             random_world()
         }
     };
 
     let camera = Camera::new(
-        Point::new(13, 2, 3),
-        Point::ZERO,
+        look_from,
+        look_at,
         Vec3::UP,
         fov,
         aspect_ratio,
@@ -177,11 +519,57 @@ fn setup_config() -> Config{
 
     let world = Bvh::new(world, 0.0, 1.0);
 
-    let c = Config::new(Box::new(world), camera, image_width, aspect_ratio);
-    c.set_samples_per_pixel(100)
+    let photon_mapper = PhotonMapper::new(1_000_000, photon_radius);
+    let photon_mapper = match photon_focus {
+        Some(f) => photon_mapper.set_focus(f),
+        None => photon_mapper,
+    };
+
+    let c = Config::new(Box::new(world), camera, image_width, aspect_ratio)
+        .set_samples_per_pixel(100)
+        .set_background(background)
+        .set_lights(lights)
+        .set_integrator(select_integrator(integrator, photon_mapper));
+
+    match atmosphere {
+        Some(a) => c.set_atmosphere(a),
+        None => c,
+    }
 }
 
+// Picks an integrator by name, so scenes can be checked without editing code. The photon
+// mappers use the photon settings for the scene.
+fn select_integrator(name: &str, photon_mapper: PhotonMapper) -> Box<dyn Integrator> {
+    match name {
+        "photons" => Box::new(photon_mapper),
+        "ppm" => Box::new(photon_mapper.set_progressive(20, 0.7)),
+        "bdpt" => Box::new(BidirectionalPathTracer::new()),
+        "spectral" => Box::new(SpectralPathTracer::new()),
+        "whitted" => Box::new(Whitted::new()),
+        "ao" => Box::new(AmbientOcclusion::new(100.0)),
+        "normals" => Box::new(DebugIntegrator::new(DebugView::Normals)),
+        "uv" => Box::new(DebugIntegrator::new(DebugView::Uvs)),
+        "depth" => Box::new(DebugIntegrator::new(DebugView::Depth(2000.0))),
+        "albedo" => Box::new(DebugIntegrator::new(DebugView::Albedo)),
+        "material" => Box::new(DebugIntegrator::new(DebugView::MaterialId)),
+        _ => Box::new(PathTracer::new()),
+    }
+}
+
+// Usage: ray_tracer [output path] [integrator] [format]
+// The format defaults to the one the extension names, and png16 writes a 16-bit PNG.
 fn main() {
-    let config = setup_config();
-    render_image(config);
+    let output_path = env::args().nth(1).unwrap_or_else(|| String::from("output/render.png"));
+    let integrator = env::args().nth(2).unwrap_or_default();
+    let format = env::args()
+        .nth(3)
+        .map(|name| OutputFormat::from_name(&name).expect("Unknown output format"));
+    let config = setup_config(&integrator);
+
+    let framebuffer = render_image(config);
+    match format {
+        Some(format) => save_image_with_format(&framebuffer, &output_path, format),
+        None => save_image(&framebuffer, &output_path),
+    }
+    .expect("Failed to save image");
 }
@@ -0,0 +1,197 @@
+// Piecewise constant distributions over [0, 1), used to importance sample tabulated functions
+pub struct Distribution1D {
+    func: Vec<f64>,
+    cdf: Vec<f64>,
+    func_int: f64,
+}
+
+impl Distribution1D {
+    pub fn new(func: Vec<f64>) -> Self {
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+
+        for i in 1..=n {
+            cdf[i] = cdf[i - 1] + func[i - 1].max(0.0) / n as f64;
+        }
+
+        let func_int = cdf[n];
+        for (i, c) in cdf.iter_mut().enumerate().skip(1) {
+            *c = if func_int == 0.0 {
+                i as f64 / n as f64
+            } else {
+                *c / func_int
+            };
+        }
+
+        Distribution1D {
+            func,
+            cdf,
+            func_int,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn integral(&self) -> f64 {
+        self.func_int
+    }
+
+    // Returns the sampled position, its probability density and the segment it fell in
+    pub fn sample_continuous(&self, u: f64) -> (f64, f64, usize) {
+        let offset = self.find_segment(u);
+
+        let mut du = u - self.cdf[offset];
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        if width > 0.0 {
+            du /= width;
+        }
+
+        let x = (offset as f64 + du) / self.count() as f64;
+        (x, self.pdf(offset), offset)
+    }
+
+    // Returns the sampled segment and the probability of choosing it
+    pub fn sample_discrete(&self, u: f64) -> (usize, f64) {
+        let offset = self.find_segment(u);
+        (offset, self.pdf(offset) / self.count() as f64)
+    }
+
+    // Density with respect to the continuous [0, 1) domain
+    pub fn pdf(&self, index: usize) -> f64 {
+        if self.func_int == 0.0 {
+            1.0
+        } else {
+            self.func[index].max(0.0) / self.func_int
+        }
+    }
+
+    fn find_segment(&self, u: f64) -> usize {
+        // The last entry in the cdf that is less than or equal to u
+        let upper = self.cdf.partition_point(|c| *c <= u);
+        upper.saturating_sub(1).min(self.count() - 1)
+    }
+}
+
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    // Values are given row by row, with `width` entries in each row
+    pub fn new(values: &[f64], width: usize, height: usize) -> Self {
+        let conditional: Vec<Distribution1D> = (0..height)
+            .map(|row| Distribution1D::new(values[row * width..(row + 1) * width].to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|d| d.integral()).collect());
+
+        Distribution2D {
+            conditional,
+            marginal,
+        }
+    }
+
+    // Returns a point in [0, 1)^2, as (column, row), and its probability density
+    pub fn sample_continuous(&self, u0: f64, u1: f64) -> ((f64, f64), f64) {
+        let (row, pdf_row, row_index) = self.marginal.sample_continuous(u1);
+        let (column, pdf_column, _) = self.conditional[row_index].sample_continuous(u0);
+
+        ((column, row), pdf_row * pdf_column)
+    }
+
+    pub fn pdf(&self, column: f64, row: f64) -> f64 {
+        let row_index = ((row * self.marginal.count() as f64) as usize).min(self.marginal.count() - 1);
+        let conditional = &self.conditional[row_index];
+        let column_index = ((column * conditional.count() as f64) as usize).min(conditional.count() - 1);
+
+        if self.marginal.integral() == 0.0 {
+            return 1.0;
+        }
+
+        conditional.func[column_index].max(0.0) / self.marginal.integral()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Maps a sampled position back to the random number which produced it
+    fn invert(distribution: &Distribution1D, x: f64) -> f64 {
+        let scaled = x * distribution.count() as f64;
+        let index = (scaled as usize).min(distribution.count() - 1);
+        let width = distribution.cdf[index + 1] - distribution.cdf[index];
+        distribution.cdf[index] + (scaled - index as f64) * width
+    }
+
+    #[test]
+    fn one_dimensional_sampling_matches_the_table() {
+        let distribution = Distribution1D::new(vec![1.0, 3.0, 0.0, 4.0]);
+        assert!((distribution.integral() - 2.0).abs() < 1e-12);
+
+        let (x, pdf, index) = distribution.sample_continuous(0.3);
+        assert_eq!(index, 1);
+        assert!((x - (1.0 + 0.175 / 0.375) / 4.0).abs() < 1e-12);
+        assert!((pdf - 1.5).abs() < 1e-12);
+
+        for i in 0..100 {
+            let u = i as f64 / 100.0;
+            let (x, pdf, index) = distribution.sample_continuous(u);
+            assert_ne!(index, 2);
+            assert_eq!(index, (x * 4.0) as usize);
+            assert!((pdf - distribution.pdf(index)).abs() < 1e-12);
+            assert!((invert(&distribution, x) - u).abs() < 1e-12);
+
+            let (discrete, probability) = distribution.sample_discrete(u);
+            assert_eq!(discrete, index);
+            assert!((probability - pdf / 4.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn all_zero_table_is_sampled_uniformly() {
+        let distribution = Distribution1D::new(vec![0.0; 4]);
+        assert_eq!(distribution.integral(), 0.0);
+
+        for i in 0..100 {
+            let u = i as f64 / 100.0;
+            let (x, pdf, _) = distribution.sample_continuous(u);
+            assert!((x - u).abs() < 1e-12);
+            assert_eq!(pdf, 1.0);
+        }
+    }
+
+    #[test]
+    fn two_dimensional_sampling_matches_the_pdf() {
+        // The middle row is all zero, so it is never sampled
+        let values = [1.0, 3.0, 0.0, 0.0, 2.0, 2.0];
+        let distribution = Distribution2D::new(&values, 2, 3);
+
+        let ((column, row), pdf) = distribution.sample_continuous(0.5, 0.25);
+        assert!((column - (1.0 + 0.25 / 0.75) / 2.0).abs() < 1e-12);
+        assert!((row - 0.5 / 3.0).abs() < 1e-12);
+        assert!((pdf - 2.25).abs() < 1e-12);
+
+        for i in 0..50 {
+            for j in 0..50 {
+                let (u0, u1) = (i as f64 / 50.0, j as f64 / 50.0);
+                let ((column, row), pdf) = distribution.sample_continuous(u0, u1);
+                assert!(!(1.0 / 3.0..2.0 / 3.0).contains(&row));
+                assert!((pdf - distribution.pdf(column, row)).abs() < 1e-12);
+
+                let row_index = (row * 3.0) as usize;
+                assert!((invert(&distribution.marginal, row) - u1).abs() < 1e-12);
+                assert!((invert(&distribution.conditional[row_index], column) - u0).abs() < 1e-12);
+            }
+        }
+
+        // The pdf integrates to one over the cells
+        let total: f64 = (0..6)
+            .map(|cell| distribution.pdf((cell % 2) as f64 / 2.0 + 0.25, (cell / 2) as f64 / 3.0 + 0.1))
+            .sum::<f64>()
+            / 6.0;
+        assert!((total - 1.0).abs() < 1e-12);
+    }
+}
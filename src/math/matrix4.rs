@@ -0,0 +1,325 @@
+use std::{f64::consts::PI, ops::Mul};
+
+use super::{Point, Vec3};
+
+// A row major 4x4 matrix for affine transforms of points, vectors and normals
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub const IDENTITY: Matrix4 = Matrix4 {
+        m: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Matrix4 { m }
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        Matrix4::new([
+            [1.0, 0.0, 0.0, offset.x],
+            [0.0, 1.0, 0.0, offset.y],
+            [0.0, 0.0, 1.0, offset.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scaling(scale: Vec3) -> Self {
+        Matrix4::new([
+            [scale.x, 0.0, 0.0, 0.0],
+            [0.0, scale.y, 0.0, 0.0],
+            [0.0, 0.0, scale.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Rotates counter clockwise about an axis through the origin, by an angle in degrees
+    pub fn rotation<T>(axis: Vec3, degrees: T) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        let a = axis.normalize();
+        let (sin, cos) = (degrees.into() * PI / 180.0).sin_cos();
+        let t = 1.0 - cos;
+
+        Matrix4::new([
+            [t * a.x * a.x + cos, t * a.x * a.y - sin * a.z, t * a.x * a.z + sin * a.y, 0.0],
+            [t * a.x * a.y + sin * a.z, t * a.y * a.y + cos, t * a.y * a.z - sin * a.x, 0.0],
+            [t * a.x * a.z - sin * a.y, t * a.y * a.z + sin * a.x, t * a.z * a.z + cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_x<T>(degrees: T) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        Matrix4::rotation(Vec3::RIGHT, degrees)
+    }
+
+    pub fn rotation_y<T>(degrees: T) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        Matrix4::rotation(Vec3::UP, degrees)
+    }
+
+    pub fn rotation_z<T>(degrees: T) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        Matrix4::rotation(Vec3::FORWARD, degrees)
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Matrix4::new(m)
+    }
+
+    // Gauss-Jordan elimination with partial pivoting, None when the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inverse = Matrix4::IDENTITY.m;
+
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))
+                .unwrap();
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let scale = 1.0 / a[column][column];
+            for k in 0..4 {
+                a[column][k] *= scale;
+                inverse[column][k] *= scale;
+            }
+
+            for row in 0..4 {
+                if row != column {
+                    let factor = a[row][column];
+                    for k in 0..4 {
+                        a[row][k] -= factor * a[column][k];
+                        inverse[row][k] -= factor * inverse[column][k];
+                    }
+                }
+            }
+        }
+
+        Some(Matrix4::new(inverse))
+    }
+
+    // The determinant of the upper left 3x3 part, which is how much volumes are scaled by
+    pub fn linear_determinant(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    // The most any vector is stretched by, which is the largest singular value of the upper
+    // left 3x3 part. It is the square root of the largest eigenvalue of that part times its
+    // transpose, found with the closed form for symmetric 3x3 matrices.
+    pub fn max_stretch(&self) -> f64 {
+        let m = &self.m;
+        let mut b = [[0.0; 3]; 3];
+        for (i, row) in b.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..3).map(|k| m[i][k] * m[j][k]).sum();
+            }
+        }
+
+        let off_diagonal = b[0][1] * b[0][1] + b[0][2] * b[0][2] + b[1][2] * b[1][2];
+        if off_diagonal == 0.0 {
+            return b[0][0].max(b[1][1]).max(b[2][2]).sqrt();
+        }
+
+        let q = (b[0][0] + b[1][1] + b[2][2]) / 3.0;
+        let p = (((b[0][0] - q).powi(2) + (b[1][1] - q).powi(2) + (b[2][2] - q).powi(2) + 2.0 * off_diagonal) / 6.0).sqrt();
+        let c = |i: usize, j: usize| (b[i][j] - if i == j { q } else { 0.0 }) / p;
+        let half_determinant = (c(0, 0) * (c(1, 1) * c(2, 2) - c(1, 2) * c(2, 1))
+            - c(0, 1) * (c(1, 0) * c(2, 2) - c(1, 2) * c(2, 0))
+            + c(0, 2) * (c(1, 0) * c(2, 1) - c(1, 1) * c(2, 0)))
+            / 2.0;
+        let phi = half_determinant.clamp(-1.0, 1.0).acos() / 3.0;
+
+        (q + 2.0 * p * phi.cos()).max(0.0).sqrt()
+    }
+
+    pub fn transform_point(&self, p: Point) -> Point {
+        let m = &self.m;
+        let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3];
+        let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3];
+        let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3];
+        let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+
+        if w == 1.0 {
+            Point::new(x, y, z)
+        } else {
+            Point::new(x / w, y / w, z / w)
+        }
+    }
+
+    // Directions ignore the translation part of the matrix
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+
+    // Multiplies by the transpose of this matrix. Normals are transformed by the inverse
+    // transpose, so this should be called on the inverse of the object's transform.
+    pub fn transform_normal(&self, n: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * n.x + m[1][0] * n.y + m[2][0] * n.z,
+            m[0][1] * n.x + m[1][1] * n.y + m[2][1] * n.z,
+            m[0][2] * n.x + m[1][2] * n.y + m[2][2] * n.z,
+        )
+    }
+}
+
+impl Mul<Matrix4> for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, other: Matrix4) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Matrix4::new(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: &Matrix4, b: &Matrix4) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((a.m[i][j] - b.m[i][j]).abs() < 1e-9, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    fn assert_near_vec(a: Vec3, b: Vec3) {
+        assert!((a - b).near_zero(), "{:?} != {:?}", a, b);
+    }
+
+    fn affine() -> Matrix4 {
+        Matrix4::translation(Vec3::new(3.0, -2.0, 5.0))
+            * Matrix4::rotation(Vec3::new(1.0, 2.0, 3.0), 37.0)
+            * Matrix4::scaling(Vec3::new(2.0, 0.5, 4.0))
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let m = affine();
+        let inverse = m.inverse().unwrap();
+        assert_near(&(m * inverse), &Matrix4::IDENTITY);
+        assert_near(&(inverse * m), &Matrix4::IDENTITY);
+
+        let p = Point::new(0.3, -1.2, 7.0);
+        assert_near_vec(inverse.transform_point(m.transform_point(p)), p);
+    }
+
+    #[test]
+    fn inverse_pivots_past_zeros_on_the_diagonal() {
+        let swap = Matrix4::new([
+            [0.0, 1.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 2.0],
+            [0.0, 0.0, 1.0, 0.0],
+        ]);
+        assert_near(&(swap * swap.inverse().unwrap()), &Matrix4::IDENTITY);
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        assert!(Matrix4::scaling(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+
+        let repeated_row = Matrix4::new([
+            [1.0, 2.0, 3.0, 4.0],
+            [2.0, 4.0, 6.0, 8.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert!(repeated_row.inverse().is_none());
+    }
+
+    #[test]
+    fn rotations_turn_counter_clockwise() {
+        assert_near_vec(Matrix4::rotation_x(90).transform_vector(Vec3::UP), Vec3::FORWARD);
+        assert_near_vec(Matrix4::rotation_y(90).transform_vector(Vec3::FORWARD), Vec3::RIGHT);
+        assert_near_vec(Matrix4::rotation_z(90).transform_vector(Vec3::RIGHT), Vec3::UP);
+
+        let axis = Vec3::new(1.0, 1.0, 1.0);
+        assert_near_vec(Matrix4::rotation(axis, 120).transform_vector(Vec3::RIGHT), Vec3::UP);
+        assert_near_vec(Matrix4::rotation(axis, 45).transform_vector(axis), axis);
+    }
+
+    #[test]
+    fn translations_move_points_but_not_vectors() {
+        let m = Matrix4::translation(Vec3::new(1.0, 2.0, 3.0));
+        assert_near_vec(m.transform_point(Point::ZERO), Point::new(1.0, 2.0, 3.0));
+        assert_near_vec(m.transform_vector(Vec3::RIGHT), Vec3::RIGHT);
+    }
+
+    #[test]
+    fn max_stretch_is_the_largest_singular_value() {
+        assert!((Matrix4::IDENTITY.max_stretch() - 1.0).abs() < 1e-12);
+        assert!((Matrix4::translation(Vec3::ONE).max_stretch() - 1.0).abs() < 1e-12);
+
+        let m = affine();
+        assert!((m.max_stretch() - 4.0).abs() < 1e-9);
+        assert!((m.inverse().unwrap().max_stretch() - 2.0).abs() < 1e-9);
+        assert!((m.linear_determinant() - 4.0).abs() < 1e-9);
+
+        // A shear stretches most along a diagonal, by the golden ratio
+        let shear = Matrix4::new([
+            [1.0, 1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert!((shear.max_stretch() - (1.0 + 5f64.sqrt()) / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scaling() {
+        let m = affine();
+        let inverse = m.inverse().unwrap();
+
+        // A plane through the origin with its normal and two tangents
+        let normal = Vec3::new(1.0, 1.0, 0.0).normalize();
+        let tangents = [Vec3::new(1.0, -1.0, 0.0), Vec3::FORWARD];
+
+        let transformed_normal = inverse.transform_normal(normal);
+        for &t in tangents.iter() {
+            let transformed_tangent = m.transform_vector(t);
+            assert!(transformed_normal.dot(transformed_tangent).abs() < 1e-9);
+        }
+
+        // Transforming the normal like a vector would tilt it off the plane
+        assert!(m.transform_vector(normal).dot(m.transform_vector(tangents[0])).abs() > 0.1);
+    }
+}
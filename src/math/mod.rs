@@ -1,5 +1,15 @@
+mod distribution;
+mod matrix4;
+mod onb;
+mod spectrum;
 mod vec3;
 
+pub use distribution::{Distribution1D, Distribution2D};
+pub use matrix4::Matrix4;
+pub use onb::Onb;
+pub use spectrum::{
+    cie_xyz, flat_spectrum_rgb, xyz_to_rgb, SampledSpectrum, SampledWavelengths, LAMBDA_MAX, LAMBDA_MIN, SPECTRUM_SAMPLES,
+};
 pub use vec3::Vec3;
 pub use vec3::Axis;
 pub type Color = Vec3;
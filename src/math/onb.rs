@@ -0,0 +1,38 @@
+use super::Vec3;
+
+// An orthonormal basis around a direction, for turning samples made about the z axis
+// into samples about that direction
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn new_from_w(direction: Vec3) -> Self {
+        let w = direction.normalize();
+        let helper = if w.x.abs() > 0.9 { Vec3::UP } else { Vec3::RIGHT };
+        let v = w.cross(helper).normalize();
+        let u = w.cross(v);
+
+        Onb { u, v, w }
+    }
+
+    // A basis around `direction` whose u follows `tangent` as closely as it can, for
+    // orienting samples along a surface. Tangents along the direction are ignored.
+    pub fn new_from_w_and_u(direction: Vec3, tangent: Vec3) -> Self {
+        let w = direction.normalize();
+        let projected = tangent - tangent.dot(w) * w;
+        if projected.length_squared() <= 1e-12 * tangent.length_squared() {
+            return Onb::new_from_w(w);
+        }
+
+        let u = projected.normalize();
+        let v = w.cross(u);
+        Onb { u, v, w }
+    }
+
+    pub fn local(&self, a: Vec3) -> Vec3 {
+        a.x * self.u + a.y * self.v + a.z * self.w
+    }
+}
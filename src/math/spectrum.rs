@@ -0,0 +1,226 @@
+use std::ops::{Add, Div, Mul};
+
+use super::{Color, Vec3};
+
+pub const LAMBDA_MIN: f64 = 360.0;
+pub const LAMBDA_MAX: f64 = 830.0;
+
+// How many wavelengths each path carries
+pub const SPECTRUM_SAMPLES: usize = 4;
+
+// The values of a spectrum at the wavelengths a path carries
+#[derive(Clone, Copy, Debug)]
+pub struct SampledSpectrum {
+    pub values: [f64; SPECTRUM_SAMPLES],
+}
+
+impl SampledSpectrum {
+    pub const ONE: SampledSpectrum = SampledSpectrum {
+        values: [1.0; SPECTRUM_SAMPLES],
+    };
+
+    pub const ZERO: SampledSpectrum = SampledSpectrum {
+        values: [0.0; SPECTRUM_SAMPLES],
+    };
+
+    // Turns an RGB reflectance or emission into a smooth spectrum by Smits' method, and
+    // looks it up at each wavelength. White becomes a flat spectrum of the same height.
+    pub fn from_rgb(rgb: Color, wavelengths: &SampledWavelengths) -> Self {
+        let mut values = [0.0; SPECTRUM_SAMPLES];
+        for (value, &lambda) in values.iter_mut().zip(wavelengths.lambda.iter()) {
+            *value = smits(rgb, lambda);
+        }
+        SampledSpectrum { values }
+    }
+
+    pub fn max_value(&self) -> f64 {
+        self.values.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.values.iter().all(|&v| v == 0.0)
+    }
+}
+
+impl Add for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn add(self, other: SampledSpectrum) -> SampledSpectrum {
+        let mut values = self.values;
+        for (value, o) in values.iter_mut().zip(other.values.iter()) {
+            *value += o;
+        }
+        SampledSpectrum { values }
+    }
+}
+
+impl Mul for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn mul(self, other: SampledSpectrum) -> SampledSpectrum {
+        let mut values = self.values;
+        for (value, o) in values.iter_mut().zip(other.values.iter()) {
+            *value *= o;
+        }
+        SampledSpectrum { values }
+    }
+}
+
+impl Mul<f64> for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn mul(self, other: f64) -> SampledSpectrum {
+        let mut values = self.values;
+        for value in values.iter_mut() {
+            *value *= other;
+        }
+        SampledSpectrum { values }
+    }
+}
+
+impl Div<f64> for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn div(self, other: f64) -> SampledSpectrum {
+        self * (1.0 / other)
+    }
+}
+
+// The wavelengths in nanometres a path carries, with the density each was picked with.
+// The first is the hero wavelength, picked uniformly over the visible range, and the rest
+// are spaced evenly after it, wrapping around at the end of the range.
+#[derive(Clone, Copy, Debug)]
+pub struct SampledWavelengths {
+    pub lambda: [f64; SPECTRUM_SAMPLES],
+    pub pdf: [f64; SPECTRUM_SAMPLES],
+}
+
+impl SampledWavelengths {
+    // Picks the hero wavelength with `u` in [0, 1)
+    pub fn sample_visible(u: f64) -> Self {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let mut lambda = [0.0; SPECTRUM_SAMPLES];
+        for (i, l) in lambda.iter_mut().enumerate() {
+            let offset = (u + i as f64 / SPECTRUM_SAMPLES as f64).fract();
+            *l = LAMBDA_MIN + offset * range;
+        }
+
+        SampledWavelengths {
+            lambda,
+            pdf: [1.0 / range; SPECTRUM_SAMPLES],
+        }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.lambda[0]
+    }
+
+    pub fn secondary_terminated(&self) -> bool {
+        self.pdf[1..].iter().all(|&p| p == 0.0)
+    }
+
+    // Drops every wavelength but the hero, for when the path has been bent by one. The
+    // hero then stands in for all of them.
+    pub fn terminate_secondary(&mut self) {
+        if self.secondary_terminated() {
+            return;
+        }
+        for pdf in self.pdf[1..].iter_mut() {
+            *pdf = 0.0;
+        }
+        self.pdf[0] /= SPECTRUM_SAMPLES as f64;
+    }
+
+    // The CIE XYZ colour of the spectrum these wavelengths were sampled from, estimated
+    // from its values at them
+    pub fn to_xyz(&self, spectrum: &SampledSpectrum) -> Vec3 {
+        let mut xyz = Vec3::ZERO;
+        for i in 0..SPECTRUM_SAMPLES {
+            if self.pdf[i] > 0.0 {
+                xyz = xyz + cie_xyz(self.lambda[i]) * (spectrum.values[i] / self.pdf[i]);
+            }
+        }
+        xyz / SPECTRUM_SAMPLES as f64
+    }
+}
+
+fn lobe(lambda: f64, mean: f64, below: f64, above: f64) -> f64 {
+    let width = if lambda < mean { below } else { above };
+    let x = (lambda - mean) / width;
+    (-0.5 * x * x).exp()
+}
+
+// The CIE 1931 colour matching functions, from the multi-lobe fit of Wyman, Sloan and
+// Shirley
+pub fn cie_xyz(lambda: f64) -> Vec3 {
+    Vec3::new(
+        1.056 * lobe(lambda, 599.8, 37.9, 31.0) + 0.362 * lobe(lambda, 442.0, 16.0, 26.7)
+            - 0.065 * lobe(lambda, 501.1, 20.4, 26.2),
+        0.821 * lobe(lambda, 568.8, 46.9, 40.5) + 0.286 * lobe(lambda, 530.9, 16.3, 31.1),
+        1.217 * lobe(lambda, 437.0, 11.8, 36.0) + 0.681 * lobe(lambda, 459.0, 26.0, 13.8),
+    )
+}
+
+// Linear sRGB from XYZ, with a D65 white point
+pub fn xyz_to_rgb(xyz: Vec3) -> Color {
+    Color::new(
+        3.2404542 * xyz.x - 1.5371385 * xyz.y - 0.4985314 * xyz.z,
+        -0.9692660 * xyz.x + 1.8760108 * xyz.y + 0.0415560 * xyz.z,
+        0.0556434 * xyz.x - 0.2040259 * xyz.y + 1.0572252 * xyz.z,
+    )
+}
+
+// The RGB colour of a flat spectrum of height one, found by integrating the colour
+// matching functions. Dividing by it keeps white white, as the RGB integrators have it.
+pub fn flat_spectrum_rgb() -> Color {
+    let steps = (LAMBDA_MAX - LAMBDA_MIN) as usize;
+    let xyz = (0..steps).fold(Vec3::ZERO, |sum, i| sum + cie_xyz(LAMBDA_MIN + i as f64 + 0.5));
+    xyz_to_rgb(xyz)
+}
+
+// Smits' basis spectra, in ten bins spanning 380 to 720 nm
+const SMITS_WHITE: [f64; 10] = [1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000];
+const SMITS_CYAN: [f64; 10] = [0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000];
+const SMITS_MAGENTA: [f64; 10] = [1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959];
+const SMITS_YELLOW: [f64; 10] = [0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840];
+const SMITS_RED: [f64; 10] = [0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149];
+const SMITS_GREEN: [f64; 10] = [0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025];
+const SMITS_BLUE: [f64; 10] = [1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496];
+
+// Interpolates between bin centres, holding the end bins beyond them
+fn basis(spectrum: &[f64; 10], lambda: f64) -> f64 {
+    let x = ((lambda - 380.0) / 34.0 - 0.5).clamp(0.0, 9.0);
+    let i = (x as usize).min(8);
+    let t = x - i as f64;
+    spectrum[i] * (1.0 - t) + spectrum[i + 1] * t
+}
+
+// Builds the spectrum from white plus the primary and secondary colours, using as much
+// white as possible
+fn smits(rgb: Color, lambda: f64) -> f64 {
+    let (r, g, b) = (rgb.x, rgb.y, rgb.z);
+    let at = |s: &[f64; 10]| basis(s, lambda);
+
+    if r <= g && r <= b {
+        let white = r * at(&SMITS_WHITE);
+        if g <= b {
+            white + (g - r) * at(&SMITS_CYAN) + (b - g) * at(&SMITS_BLUE)
+        } else {
+            white + (b - r) * at(&SMITS_CYAN) + (g - b) * at(&SMITS_GREEN)
+        }
+    } else if g <= r && g <= b {
+        let white = g * at(&SMITS_WHITE);
+        if r <= b {
+            white + (r - g) * at(&SMITS_MAGENTA) + (b - r) * at(&SMITS_BLUE)
+        } else {
+            white + (b - g) * at(&SMITS_MAGENTA) + (r - b) * at(&SMITS_RED)
+        }
+    } else {
+        let white = b * at(&SMITS_WHITE);
+        if r <= g {
+            white + (r - b) * at(&SMITS_YELLOW) + (g - r) * at(&SMITS_GREEN)
+        } else {
+            white + (g - b) * at(&SMITS_YELLOW) + (r - g) * at(&SMITS_RED)
+        }
+    }
+}
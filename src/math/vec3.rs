@@ -4,7 +4,7 @@ use rand::Rng;
 use rand::distributions::Standard;
 use rand::prelude::Distribution;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Axis {
   X,
   Y,
@@ -113,21 +113,9 @@ impl Vec3 {
         }
     }
 
-    pub fn write_color<T>(self, samples_per_pixel: T)
-    where
-        T: Into<f64> + Copy,
-    {
-        let scale = 1.0 / samples_per_pixel.into();
-
-        let scaled_r = (self.x * scale).sqrt();
-        let scaled_g = (self.y * scale).sqrt();
-        let scaled_b = (self.z * scale).sqrt();
-
-        let ir = (255.999 * scaled_r.clamp(0.0, 0.999)) as u8;
-        let ig = (255.999 * scaled_g.clamp(0.0, 0.999)) as u8;
-        let ib = (255.999 * scaled_b.clamp(0.0, 0.999)) as u8;
-
-        println!("{} {} {}", ir, ig, ib);
+    // Relative luminance, treating the vector as a linear sRGB colour
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
     }
 
     pub fn length_squared(&self) -> f64 {
@@ -172,7 +160,7 @@ impl Vec3 {
     pub fn refract(&self, normal: Vec3, ratio: f64) -> Vec3 {
         let cos_theta = (-self).dot(normal).min(1.0);
         let perp = ratio * (self + cos_theta * normal);
-        let parallel = (1.0 - perp.length_squared()).abs().sqrt() * -1.0 * normal;
+        let parallel = -(1.0 - perp.length_squared()).abs().sqrt() * normal;
 
         perp + parallel
     }
@@ -0,0 +1,30 @@
+use crate::math::Color;
+
+// The piecewise sRGB transfer function, taking linear light to encoded values
+pub fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        12.92 * value
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn encode_channel(value: f64) -> f64 {
+    linear_to_srgb(value.clamp(0.0, 1.0))
+}
+
+pub fn to_rgb8(color: Color) -> [u8; 3] {
+    [
+        (255.0 * encode_channel(color.x)).round() as u8,
+        (255.0 * encode_channel(color.y)).round() as u8,
+        (255.0 * encode_channel(color.z)).round() as u8,
+    ]
+}
+
+pub fn to_rgb16(color: Color) -> [u16; 3] {
+    [
+        (65535.0 * encode_channel(color.x)).round() as u16,
+        (65535.0 * encode_channel(color.y)).round() as u16,
+        (65535.0 * encode_channel(color.z)).round() as u16,
+    ]
+}
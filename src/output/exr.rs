@@ -0,0 +1,196 @@
+use std::io::{self, Write};
+
+use super::Framebuffer;
+
+const MAGIC_NUMBER: i32 = 20000630;
+const VERSION: i32 = 2;
+
+// OpenEXR pixel type for 32-bit floating point channels
+const PIXEL_TYPE_FLOAT: i32 = 2;
+
+fn push_attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+// Writes an uncompressed, single part, scanline OpenEXR image with 32-bit float
+// R, G and B channels. Values are written unclamped, exactly as they are stored.
+pub fn write_exr<W: Write>(framebuffer: &Framebuffer, writer: &mut W) -> io::Result<()> {
+    let width = framebuffer.width() as i32;
+    let height = framebuffer.height() as i32;
+
+    // Channels must be listed in alphabetical order
+    let mut channels = Vec::new();
+    for name in ["B", "G", "R"].iter() {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&PIXEL_TYPE_FLOAT.to_le_bytes());
+        // pLinear and three reserved bytes
+        channels.extend_from_slice(&[0, 0, 0, 0]);
+        // x and y sampling
+        channels.extend_from_slice(&1i32.to_le_bytes());
+        channels.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channels.push(0);
+
+    let mut window = Vec::new();
+    for value in [0, 0, width - 1, height - 1].iter() {
+        window.extend_from_slice(&value.to_le_bytes());
+    }
+
+    let mut header = Vec::new();
+    push_attribute(&mut header, "channels", "chlist", &channels);
+    push_attribute(&mut header, "compression", "compression", &[0]);
+    push_attribute(&mut header, "dataWindow", "box2i", &window);
+    push_attribute(&mut header, "displayWindow", "box2i", &window);
+    push_attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    push_attribute(&mut header, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    push_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    push_attribute(&mut header, "screenWindowWidth", "float", &1f32.to_le_bytes());
+    header.push(0);
+
+    writer.write_all(&MAGIC_NUMBER.to_le_bytes())?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&header)?;
+
+    // Every scanline is its own block: y coordinate, data size, then each channel in turn
+    let block_data_size = width as usize * 3 * 4;
+    let block_size = 4 + 4 + block_data_size;
+    let offset_table_size = height as usize * 8;
+
+    for y in 0..height as usize {
+        let offset = (8 + header.len() + offset_table_size + y * block_size) as u64;
+        writer.write_all(&offset.to_le_bytes())?;
+    }
+
+    for y in 0..framebuffer.height() {
+        writer.write_all(&(y as i32).to_le_bytes())?;
+        writer.write_all(&(block_data_size as i32).to_le_bytes())?;
+
+        for channel in 0..3 {
+            for x in 0..framebuffer.width() {
+                let pixel = framebuffer.get_pixel(x, y);
+                let value = match channel {
+                    0 => pixel.z,
+                    1 => pixel.y,
+                    _ => pixel.x,
+                };
+                writer.write_all(&(value as f32).to_le_bytes())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::convert::TryInto;
+
+    use crate::math::Color;
+
+    use super::*;
+
+    fn read_i32(data: &[u8], offset: usize) -> i32 {
+        i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn read_f32(data: &[u8], offset: usize) -> f32 {
+        f32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn read_string(data: &[u8], offset: &mut usize) -> String {
+        let end = *offset + data[*offset..].iter().position(|&b| b == 0).unwrap();
+        let string = String::from_utf8(data[*offset..end].to_vec()).unwrap();
+        *offset = end + 1;
+        string
+    }
+
+    #[test]
+    fn writes_a_valid_scanline_image() {
+        let pixels = vec![
+            Color::new(0.1, 0.2, 0.3),
+            Color::new(1.5, 2.5, 3.5),
+            Color::new(-1.0, 0.0, 1.0),
+            Color::new(10.0, 20.0, 30.0),
+        ];
+        let framebuffer = Framebuffer::from_pixels(2, 2, pixels.clone());
+
+        let mut data = Vec::new();
+        write_exr(&framebuffer, &mut data).unwrap();
+
+        assert_eq!(read_i32(&data, 0), 20000630);
+        assert_eq!(read_i32(&data, 4), 2);
+
+        // Attributes, as name, type and value, up to the empty name ending the header
+        let mut attributes = HashMap::new();
+        let mut offset = 8;
+        loop {
+            let name = read_string(&data, &mut offset);
+            if name.is_empty() {
+                break;
+            }
+            let kind = read_string(&data, &mut offset);
+            let size = read_i32(&data, offset) as usize;
+            attributes.insert(name, (kind, data[offset + 4..offset + 4 + size].to_vec()));
+            offset += 4 + size;
+        }
+
+        for &(name, kind) in [
+            ("channels", "chlist"),
+            ("compression", "compression"),
+            ("dataWindow", "box2i"),
+            ("displayWindow", "box2i"),
+            ("lineOrder", "lineOrder"),
+            ("pixelAspectRatio", "float"),
+            ("screenWindowCenter", "v2f"),
+            ("screenWindowWidth", "float"),
+        ]
+        .iter()
+        {
+            assert_eq!(attributes[name].0, kind, "type of {}", name);
+        }
+        assert_eq!(attributes["compression"].1, vec![0]);
+        let window: Vec<i32> = (0..4).map(|i| read_i32(&attributes["dataWindow"].1, 4 * i)).collect();
+        assert_eq!(window, vec![0, 0, 1, 1]);
+
+        // Channels are listed in alphabetical order, each as 32-bit floats
+        let mut channels = Vec::new();
+        let list = &attributes["channels"].1;
+        let mut channel_offset = 0;
+        loop {
+            let name = read_string(list, &mut channel_offset);
+            if name.is_empty() {
+                break;
+            }
+            assert_eq!(read_i32(list, channel_offset), PIXEL_TYPE_FLOAT);
+            channel_offset += 16;
+            channels.push(name);
+        }
+        assert_eq!(channels, vec!["B", "G", "R"]);
+
+        // The offset table points at each scanline, which stores its y coordinate, its size
+        // and then every pixel of each channel in the listed order
+        for y in 0..2 {
+            let block = u64::from_le_bytes(data[offset + 8 * y..offset + 8 * y + 8].try_into().unwrap()) as usize;
+            assert_eq!(read_i32(&data, block), y as i32);
+            assert_eq!(read_i32(&data, block + 4), 2 * 3 * 4);
+
+            for x in 0..2 {
+                let pixel = pixels[2 * y + x];
+                let value = |channel: usize| read_f32(&data, block + 8 + 4 * (2 * channel + x));
+                assert_eq!(value(0), pixel.z as f32);
+                assert_eq!(value(1), pixel.y as f32);
+                assert_eq!(value(2), pixel.x as f32);
+            }
+        }
+
+        let last_block = u64::from_le_bytes(data[offset + 8..offset + 16].try_into().unwrap()) as usize;
+        assert_eq!(data.len(), last_block + 8 + 2 * 3 * 4);
+    }
+}
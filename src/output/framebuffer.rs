@@ -0,0 +1,78 @@
+use crate::math::Color;
+
+use super::tone_mappers::{LinearClamp, ToneMapper};
+
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+    tone_mapper: Box<dyn ToneMapper>,
+    exposure: f64,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![Color::ZERO; (width * height) as usize],
+            tone_mapper: Box::new(LinearClamp::new()),
+            exposure: 0.0,
+        }
+    }
+
+    // Pixels are stored row by row, starting from the top left of the image
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        if pixels.len() != (width * height) as usize {
+            panic!("Pixel count does not match framebuffer dimensions");
+        }
+
+        Framebuffer {
+            width,
+            height,
+            pixels,
+            tone_mapper: Box::new(LinearClamp::new()),
+            exposure: 0.0,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> Color {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Color) {
+        let index = self.index(x, y);
+        self.pixels[index] = color;
+    }
+
+    pub fn set_tone_mapper(&mut self, tone_mapper: Box<dyn ToneMapper>) {
+        self.tone_mapper = tone_mapper;
+    }
+
+    // Exposure is measured in stops, so each extra unit doubles the brightness
+    pub fn set_exposure(&mut self, exposure: f64) {
+        self.exposure = exposure;
+    }
+
+    // Display referred linear values for every pixel, used by the low dynamic range writers
+    pub fn tone_mapped_pixels(&self) -> impl Iterator<Item = Color> + '_ {
+        let scale = 2f64.powf(self.exposure);
+        self.pixels.iter().map(move |p| self.tone_mapper.map(*p * scale))
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        (y * self.width + x) as usize
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::Path,
+};
+
+use image::{
+    ColorType, ImageBuffer, ImageError, ImageFormat, ImageResult, Rgb,
+    codecs::{
+        hdr::HdrEncoder,
+        pnm::{PNMSubtype, PnmEncoder, SampleEncoding},
+    },
+    error::{ImageFormatHint, UnsupportedError},
+};
+
+use super::{Framebuffer, to_rgb16, to_rgb8, write_exr};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Png,
+    Png16,
+    Jpeg,
+    Ppm,
+    Hdr,
+    Exr,
+}
+
+impl OutputFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<OutputFormat> {
+        OutputFormat::from_name(path.as_ref().extension()?.to_str()?)
+    }
+
+    // Names are the file extensions, along with png16 for 16-bit PNGs
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Some(OutputFormat::Png),
+            "png16" => Some(OutputFormat::Png16),
+            "jpg" | "jpeg" => Some(OutputFormat::Jpeg),
+            "ppm" => Some(OutputFormat::Ppm),
+            "hdr" => Some(OutputFormat::Hdr),
+            "exr" => Some(OutputFormat::Exr),
+            _ => None,
+        }
+    }
+}
+
+// Saves the framebuffer, choosing the format from the file extension.
+// 16-bit PNGs share the .png extension, so use save_image_with_format for those.
+// Missing directories on the way to the file are created.
+pub fn save_image<P: AsRef<Path>>(framebuffer: &Framebuffer, path: P) -> ImageResult<()> {
+    match OutputFormat::from_path(&path) {
+        Some(format) => save_image_with_format(framebuffer, path, format),
+        None => Err(ImageError::Unsupported(UnsupportedError::from(
+            ImageFormatHint::PathExtension(path.as_ref().to_path_buf()),
+        ))),
+    }
+}
+
+pub fn save_image_with_format<P: AsRef<Path>>(
+    framebuffer: &Framebuffer,
+    path: P,
+    format: OutputFormat,
+) -> ImageResult<()> {
+    if let Some(parent) = path.as_ref().parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    match format {
+        OutputFormat::Png16 => {
+            let buffer: Vec<u16> = framebuffer.tone_mapped_pixels().flat_map(to_rgb16).collect();
+            let image: ImageBuffer<Rgb<u16>, Vec<u16>> =
+                ImageBuffer::from_raw(framebuffer.width(), framebuffer.height(), buffer).unwrap();
+            image.save_with_format(path, ImageFormat::Png)
+        }
+        OutputFormat::Ppm => {
+            let buffer: Vec<u8> = framebuffer.tone_mapped_pixels().flat_map(to_rgb8).collect();
+            let writer = BufWriter::new(File::create(path)?);
+            PnmEncoder::new(writer)
+                .with_subtype(PNMSubtype::Pixmap(SampleEncoding::Binary))
+                .encode(&buffer[..], framebuffer.width(), framebuffer.height(), ColorType::Rgb8)
+        }
+        OutputFormat::Hdr => {
+            // Radiance HDR cannot store negative values, but the unclamped energy above 1 is kept
+            let buffer: Vec<Rgb<f32>> = framebuffer
+                .pixels()
+                .iter()
+                .map(|p| Rgb([p.x.max(0.0) as f32, p.y.max(0.0) as f32, p.z.max(0.0) as f32]))
+                .collect();
+            let writer = BufWriter::new(File::create(path)?);
+            HdrEncoder::new(writer).encode(
+                &buffer,
+                framebuffer.width() as usize,
+                framebuffer.height() as usize,
+            )
+        }
+        OutputFormat::Exr => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            write_exr(framebuffer, &mut writer)?;
+            Ok(())
+        }
+        OutputFormat::Png | OutputFormat::Jpeg => {
+            let buffer: Vec<u8> = framebuffer.tone_mapped_pixels().flat_map(to_rgb8).collect();
+            let image: ImageBuffer<Rgb<u8>, Vec<u8>> =
+                ImageBuffer::from_raw(framebuffer.width(), framebuffer.height(), buffer).unwrap();
+
+            let image_format = match format {
+                OutputFormat::Jpeg => ImageFormat::Jpeg,
+                _ => ImageFormat::Png,
+            };
+
+            image.save_with_format(path, image_format)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use image::codecs::hdr::HdrDecoder;
+
+    use crate::math::Color;
+
+    use super::*;
+
+    #[test]
+    fn formats_from_paths_and_names() {
+        assert_eq!(OutputFormat::from_path("output/render.PNG"), Some(OutputFormat::Png));
+        assert_eq!(OutputFormat::from_path("render.jpeg"), Some(OutputFormat::Jpeg));
+        assert_eq!(OutputFormat::from_path("render"), None);
+        assert_eq!(OutputFormat::from_name("png16"), Some(OutputFormat::Png16));
+        assert_eq!(OutputFormat::from_name("tga"), None);
+    }
+
+    #[test]
+    fn creates_missing_directories() {
+        let directory = std::env::temp_dir().join(format!("ray_tracer_{}", std::process::id()));
+        let path = directory.join("nested").join("render.ppm");
+
+        save_image(&Framebuffer::new(2, 2), &path).unwrap();
+        assert!(path.is_file());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn hdr_keeps_radiance_above_one() {
+        let pixels = vec![
+            Color::new(0.1, 0.2, 0.3),
+            Color::new(1.5, 2.5, 3.5),
+            Color::new(-1.0, 0.0, 1.0),
+            Color::new(10.0, 200.0, 3000.0),
+        ];
+        let directory = std::env::temp_dir().join(format!("ray_tracer_hdr_{}", std::process::id()));
+        let path = directory.join("render.hdr");
+        save_image(&Framebuffer::from_pixels(2, 2, pixels.clone()), &path).unwrap();
+
+        let decoder = HdrDecoder::new(BufReader::new(File::open(&path).unwrap())).unwrap();
+        assert_eq!((decoder.metadata().width, decoder.metadata().height), (2, 2));
+        let decoded = decoder.read_image_hdr().unwrap();
+        fs::remove_dir_all(directory).unwrap();
+
+        // Each pixel shares one exponent, so channels keep 8 bits relative to the brightest
+        for (pixel, Rgb([r, g, b])) in pixels.iter().zip(decoded) {
+            let brightest = pixel.x.max(pixel.y).max(pixel.z);
+            for &(expected, value) in [(pixel.x, r), (pixel.y, g), (pixel.z, b)].iter() {
+                assert!((expected.max(0.0) - value as f64).abs() <= brightest / 128.0);
+            }
+        }
+    }
+}
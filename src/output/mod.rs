@@ -0,0 +1,15 @@
+mod encoding;
+mod exr;
+mod framebuffer;
+mod image_writer;
+mod ppm;
+mod splat_buffer;
+
+pub mod tone_mappers;
+
+pub use encoding::{linear_to_srgb, to_rgb16, to_rgb8};
+pub use exr::write_exr;
+pub use framebuffer::Framebuffer;
+pub use image_writer::{OutputFormat, save_image, save_image_with_format};
+pub use ppm::write_ppm;
+pub use splat_buffer::SplatBuffer;
@@ -0,0 +1,14 @@
+use std::io::{self, Write};
+
+use super::{Framebuffer, to_rgb8};
+
+pub fn write_ppm<W: Write>(framebuffer: &Framebuffer, writer: &mut W) -> io::Result<()> {
+    write!(writer, "P3\n{} {}\n255\n", framebuffer.width(), framebuffer.height())?;
+
+    for pixel in framebuffer.tone_mapped_pixels() {
+        let [r, g, b] = to_rgb8(pixel);
+        writeln!(writer, "{} {} {}", r, g, b)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::math::Color;
+
+// Light added to any pixel from many threads at once, such as by light tracing. Each
+// channel holds the bits of an f64 and is added to with compare and swap, so splatting
+// never blocks. Pixels are stored row by row, starting from the top left of the image.
+pub struct SplatBuffer {
+    width: u32,
+    height: u32,
+    channels: Vec<AtomicU64>,
+}
+
+impl SplatBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        SplatBuffer {
+            width,
+            height,
+            channels: (0..width * height * 3).map(|_| AtomicU64::new(0f64.to_bits())).collect(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn add(&self, x: u32, y: u32, color: Color) {
+        let index = ((y * self.width + x) * 3) as usize;
+
+        for (channel, value) in self.channels[index..index + 3].iter().zip([color.x, color.y, color.z]) {
+            let mut current = channel.load(Ordering::Relaxed);
+            loop {
+                let sum = (f64::from_bits(current) + value).to_bits();
+                match channel.compare_exchange_weak(current, sum, Ordering::Relaxed, Ordering::Relaxed) {
+                    Ok(_) => break,
+                    Err(actual) => current = actual,
+                }
+            }
+        }
+    }
+
+    pub fn into_pixels(self) -> Vec<Color> {
+        let channels: Vec<f64> = self.channels.into_iter().map(|c| f64::from_bits(c.into_inner())).collect();
+        channels.chunks(3).map(|c| Color::new(c[0], c[1], c[2])).collect()
+    }
+}
@@ -0,0 +1,58 @@
+use crate::math::Color;
+
+use super::ToneMapper;
+
+// Stephen Hill's fit of the ACES reference rendering and output device transforms
+pub struct Aces;
+
+impl Aces {
+    pub fn new() -> Self {
+        Aces
+    }
+
+    fn input_transform(c: Color) -> Color {
+        Color::new(
+            0.59719 * c.x + 0.35458 * c.y + 0.04823 * c.z,
+            0.07600 * c.x + 0.90834 * c.y + 0.01566 * c.z,
+            0.02840 * c.x + 0.13383 * c.y + 0.83777 * c.z,
+        )
+    }
+
+    fn output_transform(c: Color) -> Color {
+        Color::new(
+            1.60475 * c.x - 0.53108 * c.y - 0.07367 * c.z,
+            -0.10208 * c.x + 1.10813 * c.y - 0.00605 * c.z,
+            -0.00327 * c.x - 0.07276 * c.y + 1.07602 * c.z,
+        )
+    }
+
+    fn rrt_and_odt_fit(v: f64) -> f64 {
+        let a = v * (v + 0.0245786) - 0.000090537;
+        let b = v * (0.983729 * v + 0.4329510) + 0.238081;
+        a / b
+    }
+}
+
+impl ToneMapper for Aces {
+    fn map(&self, color: Color) -> Color {
+        let c = Aces::input_transform(color);
+        let c = Color::new(
+            Aces::rrt_and_odt_fit(c.x),
+            Aces::rrt_and_odt_fit(c.y),
+            Aces::rrt_and_odt_fit(c.z),
+        );
+        let c = Aces::output_transform(c);
+
+        Color::new(
+            c.x.clamp(0.0, 1.0),
+            c.y.clamp(0.0, 1.0),
+            c.z.clamp(0.0, 1.0),
+        )
+    }
+}
+
+impl Default for Aces {
+    fn default() -> Self {
+        Aces::new()
+    }
+}
@@ -0,0 +1,42 @@
+use crate::math::Color;
+
+use super::ToneMapper;
+
+// Reinhard with a white point, the smallest radiance that is mapped to pure white
+pub struct ExtendedReinhard {
+    white_point: f64,
+}
+
+impl ExtendedReinhard {
+    pub fn new<T>(white_point: T) -> Self
+    where
+        T: Into<f64> + Copy,
+    {
+        // A white point of zero would divide black by zero
+        ExtendedReinhard {
+            white_point: white_point.into().max(1e-6),
+        }
+    }
+}
+
+impl ToneMapper for ExtendedReinhard {
+    fn map(&self, color: Color) -> Color {
+        let numerator = color * (color / self.white_point.powi(2) + 1.0);
+        let mapped = numerator / (color + 1.0);
+
+        Color::new(mapped.x.min(1.0), mapped.y.min(1.0), mapped.z.min(1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_white_point_maps_black_to_black() {
+        let tone_mapper = ExtendedReinhard::new(0.0);
+
+        assert!(tone_mapper.map(Color::ZERO).near_zero());
+        assert!((tone_mapper.map(Color::ONE * 0.5) - Color::ONE).near_zero());
+    }
+}
@@ -0,0 +1,50 @@
+use crate::math::Color;
+
+use super::ToneMapper;
+
+// John Hable's filmic curve from Uncharted 2
+pub struct Hable {
+    exposure_bias: f64,
+    white_point: f64,
+}
+
+impl Hable {
+    pub fn new() -> Self {
+        Hable {
+            exposure_bias: 2.0,
+            white_point: 11.2,
+        }
+    }
+
+    fn partial(x: f64) -> f64 {
+        let a = 0.15;
+        let b = 0.50;
+        let c = 0.10;
+        let d = 0.20;
+        let e = 0.02;
+        let f = 0.30;
+
+        ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+    }
+
+    fn map_channel(&self, value: f64) -> f64 {
+        let white_scale = 1.0 / Hable::partial(self.white_point);
+        (Hable::partial(value * self.exposure_bias) * white_scale).clamp(0.0, 1.0)
+    }
+}
+
+impl ToneMapper for Hable {
+    fn map(&self, color: Color) -> Color {
+        Color::new(
+            self.map_channel(color.x),
+            self.map_channel(color.y),
+            self.map_channel(color.z),
+        )
+    }
+}
+
+impl Default for Hable {
+    fn default() -> Self {
+        Hable::new()
+    }
+}
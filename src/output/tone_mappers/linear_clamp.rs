@@ -0,0 +1,27 @@
+use crate::math::Color;
+
+use super::ToneMapper;
+
+pub struct LinearClamp;
+
+impl LinearClamp {
+    pub fn new() -> Self {
+        LinearClamp
+    }
+}
+
+impl ToneMapper for LinearClamp {
+    fn map(&self, color: Color) -> Color {
+        Color::new(
+            color.x.clamp(0.0, 1.0),
+            color.y.clamp(0.0, 1.0),
+            color.z.clamp(0.0, 1.0),
+        )
+    }
+}
+
+impl Default for LinearClamp {
+    fn default() -> Self {
+        LinearClamp::new()
+    }
+}
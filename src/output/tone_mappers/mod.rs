@@ -0,0 +1,15 @@
+mod tone_mapper;
+
+mod aces;
+mod extended_reinhard;
+mod hable;
+mod linear_clamp;
+mod reinhard;
+
+pub use tone_mapper::ToneMapper;
+
+pub use aces::Aces;
+pub use extended_reinhard::ExtendedReinhard;
+pub use hable::Hable;
+pub use linear_clamp::LinearClamp;
+pub use reinhard::Reinhard;
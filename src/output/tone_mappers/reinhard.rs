@@ -0,0 +1,23 @@
+use crate::math::Color;
+
+use super::ToneMapper;
+
+pub struct Reinhard;
+
+impl Reinhard {
+    pub fn new() -> Self {
+        Reinhard
+    }
+}
+
+impl ToneMapper for Reinhard {
+    fn map(&self, color: Color) -> Color {
+        color / (color + 1.0)
+    }
+}
+
+impl Default for Reinhard {
+    fn default() -> Self {
+        Reinhard::new()
+    }
+}
@@ -0,0 +1,6 @@
+use crate::math::Color;
+
+// Maps exposed, scene referred radiance onto display referred linear values in [0, 1]
+pub trait ToneMapper: Sync + Send {
+    fn map(&self, color: Color) -> Color;
+}
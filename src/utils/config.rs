@@ -1,11 +1,20 @@
-use crate::graphics::{Camera, Hittable};
+use crate::graphics::{Camera, Hittable, backgrounds::{Background, GradientBackground}, volumes::Atmosphere};
+use crate::integrators::{Integrator, PathTracer};
+use crate::output::tone_mappers::{LinearClamp, ToneMapper};
 
 pub struct Config {
     pub camera: Camera,
     pub samples_per_pixel: u32,
     pub max_depth: u32,
+    pub russian_roulette_depth: u32,
     pub image_size: (u32, u32),
-    pub world: Box<dyn Hittable>
+    pub world: Box<dyn Hittable>,
+    pub lights: Vec<Box<dyn Hittable>>,
+    pub background: Box<dyn Background>,
+    pub atmosphere: Option<Atmosphere>,
+    pub integrator: Box<dyn Integrator>,
+    pub tone_mapper: Box<dyn ToneMapper>,
+    pub exposure: f64,
 }
 
 impl Config {
@@ -15,8 +24,15 @@ impl Config {
             camera,
             samples_per_pixel: 100,
             max_depth: 50,
+            russian_roulette_depth: 5,
             image_size: (image_width, image_height),
             world,
+            lights: Vec::new(),
+            background: Box::new(GradientBackground::sky()),
+            atmosphere: None,
+            integrator: Box::new(PathTracer::new()),
+            tone_mapper: Box::new(LinearClamp::new()),
+            exposure: 0.0,
         }
     }
 
@@ -31,4 +47,42 @@ impl Config {
      {
         Config { max_depth, ..self }
     }
+
+    // The number of bounces before paths may be ended early by Russian roulette. Setting
+    // it to max_depth or more turns Russian roulette off.
+    pub fn set_russian_roulette_depth(self, russian_roulette_depth: u32) -> Self {
+        Config {
+            russian_roulette_depth,
+            ..self
+        }
+    }
+
+    // Emitters to sample directly. These are only used to pick directions, so they should
+    // also be part of the world, for example by sharing them through an `Arc`.
+    pub fn set_lights(self, lights: Vec<Box<dyn Hittable>>) -> Self {
+        Config { lights, ..self }
+    }
+
+    pub fn set_background(self, background: Box<dyn Background>) -> Self {
+        Config { background, ..self }
+    }
+
+    pub fn set_atmosphere(self, atmosphere: Atmosphere) -> Self {
+        Config {
+            atmosphere: Some(atmosphere),
+            ..self
+        }
+    }
+
+    pub fn set_integrator(self, integrator: Box<dyn Integrator>) -> Self {
+        Config { integrator, ..self }
+    }
+
+    pub fn set_tone_mapper(self, tone_mapper: Box<dyn ToneMapper>) -> Self {
+        Config { tone_mapper, ..self }
+    }
+
+    pub fn set_exposure(self, exposure: f64) -> Self {
+        Config { exposure, ..self }
+    }
 }